@@ -40,7 +40,7 @@ from gnuradio import qtgui
 
 class replay(gr.top_block, Qt.QWidget):
 
-    def __init__(self, freq=2412e6, gain=0, rf_fn="", samp_rate=2e6):
+    def __init__(self, freq=2412e6, gain=0, loop=0, rf_fn="", samp_rate=2e6):
         gr.top_block.__init__(self, "rf_replay", catch_exceptions=True)
         Qt.QWidget.__init__(self)
         self.setWindowTitle("rf_replay")
@@ -76,6 +76,7 @@ class replay(gr.top_block, Qt.QWidget):
         ##################################################
         self.freq = freq
         self.gain = gain
+        self.loop = loop
         self.rf_fn = rf_fn
         self.samp_rate = samp_rate
 
@@ -126,7 +127,7 @@ class replay(gr.top_block, Qt.QWidget):
         self.top_layout.addWidget(self._qtgui_freq_sink_x_0_win)
         self.blocks_throttle_0 = blocks.throttle(gr.sizeof_gr_complex*1, samp_rate,True)
         self.blocks_multiply_const_xx_0 = blocks.multiply_const_cc(10**(gain/20), 1)
-        self.blocks_file_source_0 = blocks.file_source(gr.sizeof_gr_complex*1, rf_fn, False, 0, 0)
+        self.blocks_file_source_0 = blocks.file_source(gr.sizeof_gr_complex*1, rf_fn, bool(loop), 0, 0)
         self.blocks_file_source_0.set_begin_tag(pmt.PMT_NIL)
 
 
@@ -160,12 +161,19 @@ class replay(gr.top_block, Qt.QWidget):
         self.gain = gain
         self.blocks_multiply_const_xx_0.set_k(10**(self.gain/20))
 
+    def get_loop(self):
+        return self.loop
+
+    def set_loop(self, loop):
+        self.loop = loop
+        self.blocks_file_source_0.open(self.rf_fn, bool(self.loop))
+
     def get_rf_fn(self):
         return self.rf_fn
 
     def set_rf_fn(self, rf_fn):
         self.rf_fn = rf_fn
-        self.blocks_file_source_0.open(self.rf_fn, False)
+        self.blocks_file_source_0.open(self.rf_fn, bool(self.loop))
 
     def get_samp_rate(self):
         return self.samp_rate
@@ -185,6 +193,9 @@ def argument_parser():
     parser.add_argument(
         "--gain", dest="gain", type=eng_float, default=eng_notation.num_to_str(float(0)),
         help="Set gain [default=%(default)r]")
+    parser.add_argument(
+        "--loop", dest="loop", type=intx, default=0,
+        help="Set loop [default=%(default)r]")
     parser.add_argument(
         "--rf-fn", dest="rf_fn", type=str, default="",
         help="Set rf_fn [default=%(default)r]")
@@ -203,7 +214,7 @@ def main(top_block_cls=replay, options=None):
         Qt.QApplication.setGraphicsSystem(style)
     qapp = Qt.QApplication(sys.argv)
 
-    tb = top_block_cls(freq=options.freq, gain=options.gain, rf_fn=options.rf_fn, samp_rate=options.samp_rate)
+    tb = top_block_cls(freq=options.freq, gain=options.gain, loop=options.loop, rf_fn=options.rf_fn, samp_rate=options.samp_rate)
 
     tb.start()
 
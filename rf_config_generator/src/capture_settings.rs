@@ -1,32 +1,244 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashMap, fmt::Display};
 
-use crate::Message;
+use crate::{
+    Message, collection_modes,
+    device::{self, HardwareProfile},
+    tasks, utils,
+    validation::{self, ValidationIssue},
+};
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct CaptureSettings {
     sample_rates: Vec<f64>,
     durations: Vec<f64>,
     gains: Vec<f64>,
-    #[serde(skip)]
+    // Choices of the compression combo box
+    #[serde(skip, default = "compression_options")]
     compression_options: iced::widget::combo_box::State<CompressionOptions>,
-    #[serde(skip)]
-    compression_option: Option<CompressionOptions>,
+    // Display name of a CompressionOptions, empty for none
     compression: String,
     compression_level: u64,
+    // IQ sample format recordings are written in
+    #[serde(default = "default_datatype")]
+    datatype: String,
+    // How exports name output files, see tasks::FILENAME_TOKENS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filename_template: Option<String>,
+    // Range being typed under each list, in ValueList order
+    #[serde(skip)]
+    range_drafts: [RangeDraft; 3],
+    // Text last typed into each list entry, which may not parse
+    #[serde(skip)]
+    inputs: HashMap<(ValueList, usize), String>,
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
 }
 
-struct CompressionState {}
+/// Compares what is saved, leaving out the combo box and typing drafts
+impl PartialEq for CaptureSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.sample_rates == other.sample_rates
+            && self.durations == other.durations
+            && self.gains == other.gains
+            && self.compression == other.compression
+            && self.compression_level == other.compression_level
+            && self.datatype == other.datatype
+            && self.filename_template == other.filename_template
+            && self.unknown == other.unknown
+    }
+}
 
-#[derive(Debug, Clone)]
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            sample_rates: Vec::new(),
+            durations: Vec::new(),
+            gains: Vec::new(),
+            compression_options: compression_options(),
+            compression: String::new(),
+            compression_level: 0,
+            datatype: default_datatype(),
+            filename_template: None,
+            range_drafts: Default::default(),
+            inputs: HashMap::new(),
+            unknown: Default::default(),
+        }
+    }
+}
+
+/// IQ sample formats, as SigMF names them
+const DATATYPES: &[&str] = &["cf32", "ci16", "ci8"];
+
+// What the recorder writes (numpy complex64)
+fn default_datatype() -> String {
+    "cf32".to_string()
+}
+
+/// Most values one generated range may add
+const MAX_RANGE_VALUES: usize = 1000;
+
+/// One of the value lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueList {
+    SampleRates,
+    Durations,
+    Gains,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeField {
+    Start,
+    Stop,
+    Step,
+}
+
+/// Start, stop and step as typed for generating a list
+#[derive(Debug, Clone, Default)]
+struct RangeDraft {
+    start: String,
+    stop: String,
+    step: String,
+    // Adds the generated values after the list's own instead of
+    // replacing them
+    append: bool,
+}
+
+impl RangeDraft {
+    fn field(&mut self, field: RangeField) -> &mut String {
+        match field {
+            RangeField::Start => &mut self.start,
+            RangeField::Stop => &mut self.stop,
+            RangeField::Step => &mut self.step,
+        }
+    }
+
+    /// The generated values, None while a field doesn't parse
+    fn values(&self) -> Option<Result<Vec<f64>, String>> {
+        let [start, stop, step] =
+            [&self.start, &self.stop, &self.step].map(|x| utils::parse_finite(x));
+        Some(generate_range(start?, stop?, step?))
+    }
+}
+
+/// `start`, `start + step`, ... up to and including `stop` when a whole
+/// number of steps reaches it. Counting down takes a negative step. Values
+/// are rounded to 9 decimals so steps like 0.1 don't pile up float noise.
+pub fn generate_range(start: f64, stop: f64, step: f64) -> Result<Vec<f64>, String> {
+    if step == 0.0 {
+        return Err("step must not be 0".to_string());
+    }
+    if (stop - start) * step < 0.0 {
+        let direction = if stop < start {
+            "negative to count down"
+        } else {
+            "positive to count up"
+        };
+        return Err(format!("step must be {direction}"));
+    }
+    let count = ((stop - start) / step + 1e-9).floor() as usize + 1;
+    if count > MAX_RANGE_VALUES {
+        return Err(format!(
+            "that makes {count} values, more than {MAX_RANGE_VALUES}"
+        ));
+    }
+    Ok((0..count)
+        .map(|i| ((start + i as f64 * step) * 1e9).round() / 1e9)
+        .collect())
+}
+
+/// Positions of entries sharing their value with another entry. Zeros are
+/// what Add inserts, so they aren't counted until edited.
+fn duplicate_indices(values: &[f64]) -> Vec<usize> {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(i, value)| {
+            **value != 0.0
+                && values
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| j != *i && other == *value)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Advice when the compression likely costs more than it saves on the
+/// datatype. Float samples carry noise in every mantissa bit, so general
+/// purpose compressors shrink them very little.
+fn compression_advice(datatype: &str, compression: &str, level: u64) -> Option<String> {
+    let compressed = !compression.is_empty() && !compression.eq_ignore_ascii_case("none");
+    if datatype != "cf32" || !compressed {
+        return None;
+    }
+    let mut advice = format!("{compression} gains little on cf32 samples; consider no compression");
+    if level > 3 {
+        advice.push_str(" or a level of 3 or lower to save CPU time");
+    }
+    Some(advice)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CompressionOptions {
     ZStd,
+    Gzip,
+    Lz4,
+    // Recordings are written as is
+    None,
+    // Stands for an unrecognised name; never offered
+    #[allow(dead_code)]
     Invalid,
 }
 
+impl CompressionOptions {
+    /// What the compression combo box offers
+    pub const SELECTABLE: &'static [CompressionOptions] = &[
+        CompressionOptions::ZStd,
+        CompressionOptions::Gzip,
+        CompressionOptions::Lz4,
+        CompressionOptions::None,
+    ];
+
+    /// The option saved as `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::SELECTABLE
+            .iter()
+            .copied()
+            .find(|x| x.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// Levels the compressor accepts, None when it takes no level
+    pub fn level_range(self) -> Option<std::ops::RangeInclusive<u64>> {
+        match self {
+            Self::ZStd => Some(1..=22),
+            Self::Gzip => Some(0..=9),
+            // 0-2 are the fast compressor, 3 and up switch to HC
+            Self::Lz4 => Some(0..=12),
+            Self::None | Self::Invalid => None,
+        }
+    }
+
+    /// What the levels mean, where that isn't obvious
+    fn level_hint(self) -> Option<&'static str> {
+        match self {
+            Self::Lz4 => Some("0-2 fast, 3-12 high compression (HC)"),
+            _ => None,
+        }
+    }
+}
+
+fn compression_options() -> iced::widget::combo_box::State<CompressionOptions> {
+    iced::widget::combo_box::State::new(CompressionOptions::SELECTABLE.to_vec())
+}
+
 impl Display for CompressionOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::ZStd => write!(f, "zstd"),
+            Self::Gzip => write!(f, "gzip"),
+            Self::Lz4 => write!(f, "lz4"),
+            Self::None => write!(f, "none"),
             Self::Invalid => write!(f, "INVALID"),
         }
     }
@@ -35,16 +247,79 @@ impl Display for CompressionOptions {
 #[derive(Debug, Clone)]
 pub enum CaptureSettingsMsg {
     AddSampleRate,
+    // Appends a value picked from the hardware profile's presets
+    AddSampleRatePreset(f64),
     UpdateSampleRate((String, usize)),
     DelSampleRate(usize),
     AddDuration,
     UpdateDuration((String, usize)),
     DelDuration(usize),
     AddGain,
+    AddGainPreset(f64),
     UpdateGain((String, usize)),
+    // Moves a gain by the dB given, see device::step_gain
+    StepGain((usize, f64)),
     DelGain(usize),
     ChangeCompression(String),
     ChangeCompressionLevel(u64),
+    StepCompressionLevel(i64),
+    ChangeDatatype(String),
+    ChangeFilenameTemplate(String),
+    // Replaces (false) or merges into (true) the settings
+    ApplyPreset((Box<CaptureSettings>, bool)),
+    RangeInput((ValueList, RangeField, String)),
+    RangeAppend((ValueList, bool)),
+    // Fills the list from its range, replacing or appending per the toggle
+    GenerateRange(ValueList),
+}
+
+/// Saving and applying named capture-settings presets
+#[derive(Debug, Clone)]
+pub enum PresetMsg {
+    Name(String),
+    Select(String),
+    Merge(bool),
+    Save,
+    Apply,
+    Delete,
+}
+
+impl From<PresetMsg> for Message {
+    fn from(value: PresetMsg) -> Self {
+        Message::Preset(value)
+    }
+}
+
+/// Inputs of the preset controls
+#[derive(Debug, Default)]
+pub struct PresetPanel {
+    pub name: String,
+    pub selected: Option<String>,
+    pub merge: bool,
+}
+
+impl PresetPanel {
+    pub fn view<'a>(&'a self, names: Vec<String>) -> iced::Element<'a, Message> {
+        let selected = self.selected.is_some();
+        iced::widget::row![
+            iced::widget::text("Presets"),
+            iced::widget::text_input("preset name", &self.name)
+                .on_input(|c| PresetMsg::Name(c).into())
+                .width(160),
+            iced::widget::button("Save Preset")
+                .on_press_maybe((!self.name.trim().is_empty()).then_some(PresetMsg::Save.into())),
+            iced::widget::pick_list(names, self.selected.clone(), |c| PresetMsg::Select(c)
+                .into())
+            .placeholder("choose preset"),
+            iced::widget::checkbox("merge", self.merge).on_toggle(|c| PresetMsg::Merge(c).into()),
+            iced::widget::button("Apply")
+                .on_press_maybe(selected.then_some(PresetMsg::Apply.into())),
+            iced::widget::button("Delete")
+                .on_press_maybe(selected.then_some(PresetMsg::Delete.into())),
+        ]
+        .spacing(10)
+        .into()
+    }
 }
 
 impl From<CaptureSettingsMsg> for Message {
@@ -53,138 +328,659 @@ impl From<CaptureSettingsMsg> for Message {
     }
 }
 
-fn change_if_valid<T: FromStr>(string: String, val: &mut T) {
-    if let Ok(parsed) = string.parse() {
-        *val = parsed;
+impl CaptureSettings {
+    pub fn is_empty(&self) -> bool {
+        self.sample_rates.is_empty() && self.durations.is_empty() && self.gains.is_empty()
     }
-}
 
-impl CaptureSettings {
-    pub fn update(&mut self, msg: Message) {
+    /// Takes on what `preset` holds: the three lists, compression and
+    /// datatype. Merging adds the preset's values missing from each list
+    /// and only fills in compression when none is set and the datatype
+    /// while it is the default; otherwise the preset's replace them. The
+    /// file name template and unknown fields are kept either way.
+    pub fn apply(&mut self, preset: CaptureSettings, merge: bool) {
+        if !merge {
+            self.sample_rates = preset.sample_rates;
+            self.durations = preset.durations;
+            self.gains = preset.gains;
+            self.inputs.clear();
+            self.compression = preset.compression;
+            self.compression_level = preset.compression_level;
+            self.datatype = preset.datatype;
+            return;
+        }
+        self.add_missing(&preset.sample_rates, &preset.durations, &preset.gains);
+        if self.compression.is_empty() {
+            self.compression = preset.compression;
+            self.compression_level = preset.compression_level;
+        }
+        if self.datatype == default_datatype() {
+            self.datatype = preset.datatype;
+        }
+    }
+
+    /// Keeps the text typed into entry `id` of `list`, taking its value
+    /// when it parses
+    fn change_if_valid(&mut self, list: ValueList, id: usize, text: String) {
+        if let Some(parsed) = utils::parse_finite(&text) {
+            self.list_mut(list)[id] = parsed;
+        }
+        self.inputs.insert((list, id), text);
+    }
+
+    /// Drops what was typed into `list`, once its entries move
+    fn forget_inputs(&mut self, list: ValueList) {
+        self.inputs.retain(|(x, _), _| *x != list);
+    }
+
+    fn input(&self, list: ValueList, id: usize) -> Option<&str> {
+        self.inputs.get(&(list, id)).map(String::as_str)
+    }
 
-        let Message::CaptureSettingsMsg(msg) = msg else {return;};
+    fn list_mut(&mut self, list: ValueList) -> &mut Vec<f64> {
+        match list {
+            ValueList::SampleRates => &mut self.sample_rates,
+            ValueList::Durations => &mut self.durations,
+            ValueList::Gains => &mut self.gains,
+        }
+    }
+
+    /// Appends the values each list doesn't hold yet
+    pub fn add_missing(&mut self, sample_rates: &[f64], durations: &[f64], gains: &[f64]) {
+        let extend = |list: &mut Vec<f64>, values: &[f64]| {
+            for value in values {
+                if !list.contains(value) {
+                    list.push(*value);
+                }
+            }
+        };
+        extend(&mut self.sample_rates, sample_rates);
+        extend(&mut self.durations, durations);
+        extend(&mut self.gains, gains);
+    }
+
+    pub fn datatype(&self) -> &str {
+        &self.datatype
+    }
+
+    /// Fills sample rates and gains with the profile's presets where none
+    /// have been entered yet
+    pub fn fill_empty(&mut self, profile: &HardwareProfile) {
+        if self.sample_rates.is_empty() {
+            self.sample_rates = profile.sample_rates.to_vec();
+        }
+        if self.gains.is_empty() {
+            self.gains = profile.gains.to_vec();
+        }
+    }
+
+    /// Entries repeating a value in each list, by position. Each repeat
+    /// only multiplies the captures without adding any.
+    pub fn duplicates(&self) -> [(&'static str, Vec<usize>); 3] {
+        [
+            ("sample rate", duplicate_indices(&self.sample_rates)),
+            ("duration", duplicate_indices(&self.durations)),
+            ("gain", duplicate_indices(&self.gains)),
+        ]
+    }
+
+    /// The lists collection modes pick their values from
+    pub fn declared(&self) -> collection_modes::Declared<'_> {
+        collection_modes::Declared {
+            sample_rates: &self.sample_rates,
+            durations: &self.durations,
+            gains: &self.gains,
+        }
+    }
+
+    /// Compression method and level applied to recordings. The level is
+    /// None when the method takes none.
+    pub fn compression(&self) -> (&str, Option<u64>) {
+        (
+            &self.compression,
+            self.uses_level().then_some(self.compression_level),
+        )
+    }
+
+    // No compression, whether chosen or left empty, has no level
+    fn uses_level(&self) -> bool {
+        !self.compression.is_empty()
+            && CompressionOptions::from_name(&self.compression) != Some(CompressionOptions::None)
+    }
+
+    /// Template output files are named by
+    pub fn filename_template(&self) -> &str {
+        self.filename_template
+            .as_deref()
+            .unwrap_or(tasks::DEFAULT_FILENAME_TEMPLATE)
+    }
+
+    /// Levels the chosen compression accepts, when it is one we know
+    pub fn compression_level_range(&self) -> Option<std::ops::RangeInclusive<u64>> {
+        CompressionOptions::from_name(&self.compression).and_then(CompressionOptions::level_range)
+    }
+
+    /// Why the compression level can't be used with the chosen
+    /// compression, if it can't
+    pub fn compression_level_issue(&self) -> Option<String> {
+        let range = self.compression_level_range()?;
+        (!range.contains(&self.compression_level)).then(|| {
+            format!(
+                "{} level {} is outside {}..={}",
+                self.compression,
+                self.compression_level,
+                range.start(),
+                range.end()
+            )
+        })
+    }
+
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = self
+            .sample_rates
+            .iter()
+            .enumerate()
+            .filter(|(_, rate)| **rate <= 0.0)
+            .map(|(i, _)| {
+                ValidationIssue::error(
+                    "Capture Settings",
+                    format!("sample rate {} must be greater than 0", i + 1),
+                )
+            })
+            .collect();
+        let lists = [&self.sample_rates, &self.durations, &self.gains];
+        for ((label, indices), values) in self.duplicates().into_iter().zip(lists) {
+            let mut repeated: Vec<f64> = Vec::new();
+            for value in indices.iter().map(|i| values[*i]) {
+                if !repeated.contains(&value) {
+                    repeated.push(value);
+                }
+            }
+            issues.extend(repeated.into_iter().map(|value| {
+                ValidationIssue::warning(
+                    "Capture Settings",
+                    format!("{label} {value} is listed more than once"),
+                )
+            }));
+        }
+        if let Some(template) = &self.filename_template {
+            issues.extend(tasks::unknown_tokens(template).into_iter().map(|token| {
+                ValidationIssue::warning(
+                    "Capture Settings",
+                    format!(
+                        "file name template has unknown token {{{token}}}, which is kept as written"
+                    ),
+                )
+            }));
+            issues.extend(validation::file_name_issue(
+                "Capture Settings",
+                "file name template",
+                template,
+            ));
+        }
+        if !self.compression.is_empty()
+            && CompressionOptions::from_name(&self.compression).is_none()
+        {
+            issues.push(ValidationIssue::error(
+                "Capture Settings",
+                format!(
+                    "compression '{}' is not one the capture tool supports",
+                    self.compression
+                ),
+            ));
+        }
+        issues.extend(
+            self.compression_level_issue()
+                .map(|issue| ValidationIssue::error("Capture Settings", issue)),
+        );
+        issues.extend(
+            compression_advice(&self.datatype, &self.compression, self.compression_level)
+                .map(|advice| ValidationIssue::warning("Capture Settings", advice)),
+        );
+        issues
+    }
+
+    /// `profile` bounds the gain steppers
+    pub fn update(&mut self, msg: Message, profile: Option<&HardwareProfile>) {
+        let Message::CaptureSettingsMsg(msg) = msg else {
+            return;
+        };
 
         match msg {
             CaptureSettingsMsg::AddSampleRate => {
                 self.sample_rates.push(0.0);
             }
+            CaptureSettingsMsg::AddSampleRatePreset(rate) => {
+                if !self.sample_rates.contains(&rate) {
+                    self.sample_rates.push(rate);
+                }
+            }
             CaptureSettingsMsg::UpdateSampleRate((val, id)) => {
-                change_if_valid(val, &mut self.sample_rates[id]);
+                self.change_if_valid(ValueList::SampleRates, id, val);
             }
             CaptureSettingsMsg::DelSampleRate(id) => {
                 self.sample_rates.remove(id);
+                self.forget_inputs(ValueList::SampleRates);
             }
             CaptureSettingsMsg::AddDuration => {
                 self.durations.push(0.0);
             }
             CaptureSettingsMsg::UpdateDuration((val, id)) => {
-                change_if_valid(val, &mut self.durations[id]);
+                self.change_if_valid(ValueList::Durations, id, val);
             }
             CaptureSettingsMsg::DelDuration(id) => {
                 self.durations.remove(id);
+                self.forget_inputs(ValueList::Durations);
             }
             CaptureSettingsMsg::AddGain => {
                 self.gains.push(0.0);
             }
+            CaptureSettingsMsg::AddGainPreset(gain) => {
+                if !self.gains.contains(&gain) {
+                    self.gains.push(gain);
+                }
+            }
             CaptureSettingsMsg::UpdateGain((val, id)) => {
-                change_if_valid(val, &mut self.gains[id]);
+                self.change_if_valid(ValueList::Gains, id, val);
+            }
+            CaptureSettingsMsg::StepGain((id, delta)) => {
+                self.gains[id] = device::step_gain(self.gains[id], delta, profile);
+                self.inputs.remove(&(ValueList::Gains, id));
             }
             CaptureSettingsMsg::DelGain(id) => {
                 self.gains.remove(id);
+                self.forget_inputs(ValueList::Gains);
             }
             CaptureSettingsMsg::ChangeCompression(option) => {
                 self.compression = option;
+                // Keep the level usable, e.g. the default 0 when picking zstd
+                if let Some(range) = self.compression_level_range() {
+                    self.compression_level =
+                        self.compression_level.clamp(*range.start(), *range.end());
+                }
             }
             CaptureSettingsMsg::ChangeCompressionLevel(val) => {
                 self.compression_level = val;
             }
+            CaptureSettingsMsg::StepCompressionLevel(delta) => {
+                let level = self.compression_level.saturating_add_signed(delta);
+                self.compression_level = match self.compression_level_range() {
+                    Some(range) => level.clamp(*range.start(), *range.end()),
+                    None => level,
+                };
+            }
+            CaptureSettingsMsg::ChangeDatatype(datatype) => {
+                self.datatype = datatype;
+            }
+            CaptureSettingsMsg::ChangeFilenameTemplate(template) => {
+                self.filename_template = (!template.is_empty()).then_some(template);
+            }
+            CaptureSettingsMsg::ApplyPreset((preset, merge)) => {
+                self.apply(*preset, merge);
+            }
+            CaptureSettingsMsg::RangeInput((list, field, text)) => {
+                *self.range_drafts[list as usize].field(field) = text;
+            }
+            CaptureSettingsMsg::RangeAppend((list, append)) => {
+                self.range_drafts[list as usize].append = append;
+            }
+            CaptureSettingsMsg::GenerateRange(list) => {
+                let draft = &self.range_drafts[list as usize];
+                let append = draft.append;
+                if let Some(Ok(values)) = draft.values() {
+                    if !append {
+                        self.forget_inputs(list);
+                    }
+                    let target = self.list_mut(list);
+                    if !append {
+                        target.clear();
+                    }
+                    for value in values {
+                        if !target.contains(&value) {
+                            target.push(value);
+                        }
+                    }
+                }
+            }
         }
-
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
-        iced::widget::row![
+    /// `profile` supplies the presets offered next to each Add button.
+    /// `example` is the output file name of the config's first capture,
+    /// shown under the file name template.
+    pub fn view(
+        &self,
+        profile: Option<&HardwareProfile>,
+        example: Option<String>,
+    ) -> iced::Element<'_, Message> {
+        let compression = CompressionOptions::from_name(&self.compression);
+        let [(_, sample_rates), (_, durations), (_, gains)] = self.duplicates();
+        let presets = |values: &[f64], msg: fn(f64) -> CaptureSettingsMsg| {
+            (!values.is_empty()).then(|| {
+                iced::widget::pick_list(values.to_vec(), None::<f64>, move |c| {
+                    Message::from(msg(c))
+                })
+                .placeholder("Add preset")
+            })
+        };
+        let settings = iced::widget::row![
             iced::widget::column![
-                iced::widget::container(iced::widget::text("Sample Rates")).center(iced::Length::Shrink),
+                iced::widget::container(iced::widget::text("Sample Rates"))
+                    .center(iced::Length::Shrink),
                 iced::widget::button("Add")
                     .on_press(Message::from(CaptureSettingsMsg::AddSampleRate)),
+                self.range_widget(ValueList::SampleRates),
             ]
+            .push_maybe(presets(
+                profile.map_or(&[], |x| x.sample_rates),
+                CaptureSettingsMsg::AddSampleRatePreset,
+            ))
             .extend(
                 self.sample_rates
                     .iter()
                     .enumerate()
-                    .map(|(i, _)| self.sample_rate_widget(i))
+                    .map(|(i, _)| self.sample_rate_widget(i, sample_rates.contains(&i), profile))
             ),
             iced::widget::column![
-                iced::widget::container(iced::widget::text("Durations")).center(iced::Length::Shrink),
+                iced::widget::container(iced::widget::text("Durations"))
+                    .center(iced::Length::Shrink),
                 iced::widget::button("Add")
                     .on_press(Message::from(CaptureSettingsMsg::AddDuration)),
+                self.range_widget(ValueList::Durations),
             ]
             .extend(
                 self.durations
                     .iter()
                     .enumerate()
-                    .map(|(i, _)| self.duration_widget(i))
+                    .map(|(i, _)| self.duration_widget(i, durations.contains(&i)))
             ),
             iced::widget::column![
                 iced::widget::container(iced::widget::text("Gains")).center(iced::Length::Shrink),
                 iced::widget::button("Add").on_press(Message::from(CaptureSettingsMsg::AddGain)),
+                self.range_widget(ValueList::Gains),
             ]
+            .push_maybe(presets(
+                profile.map_or(&[], |x| x.gains),
+                CaptureSettingsMsg::AddGainPreset
+            ))
             .extend(
                 self.gains
                     .iter()
                     .enumerate()
-                    .map(|(i, _)| self.gains_widget(i))
+                    .map(|(i, _)| self.gains_widget(i, gains.contains(&i), profile))
             ),
             iced::widget::column![
-                iced::widget::text_input(
+                iced::widget::pick_list(
+                    DATATYPES.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+                    Some(self.datatype.clone()),
+                    |c| Message::from(CaptureSettingsMsg::ChangeDatatype(c)),
+                ),
+                iced::widget::combo_box(
+                    &self.compression_options,
                     "Compression Type",
-                    &self.compression
-                )
-                .on_input(|c| {
-                    Message::from(CaptureSettingsMsg::ChangeCompression(c))
-                }),
-                iced::widget::text_input(
-                    "Compression Level",
-                    &format!("{}", self.compression_level)
-                )
-                .on_input(|c| {
-                    if let Ok(parsed) = c.parse() {
-                        Message::from(CaptureSettingsMsg::ChangeCompressionLevel(parsed))
-                    } else {
-                        Message::None
-                    }
-                }),
-            ],
+                    compression.as_ref(),
+                    |c| Message::from(CaptureSettingsMsg::ChangeCompression(c.to_string())),
+                ),
+            ]
+            .push_maybe(self.uses_level().then(|| {
+                iced::widget::row![
+                    iced::widget::button("-")
+                        .on_press(Message::from(CaptureSettingsMsg::StepCompressionLevel(-1))),
+                    validation::digits_input(
+                        "Compression Level",
+                        self.compression_level,
+                        self.compression_level_range(),
+                        |c| Message::from(CaptureSettingsMsg::ChangeCompressionLevel(c)),
+                    ),
+                    iced::widget::button("+")
+                        .on_press(Message::from(CaptureSettingsMsg::StepCompressionLevel(1))),
+                ]
+            }))
+            .push_maybe(
+                compression
+                    .and_then(CompressionOptions::level_hint)
+                    .map(iced::widget::text)
+            ),
         ]
-        .spacing(10)
+        .spacing(10);
+        let tokens: Vec<String> = tasks::FILENAME_TOKENS
+            .iter()
+            .map(|x| format!("{{{x}}}"))
+            .collect();
+        let template = iced::widget::column![
+            iced::widget::text(format!("Output file names, using {}", tokens.join(" "))),
+            iced::widget::text_input(
+                tasks::DEFAULT_FILENAME_TEMPLATE,
+                self.filename_template.as_deref().unwrap_or_default(),
+            )
+            .on_input(|c| Message::from(CaptureSettingsMsg::ChangeFilenameTemplate(c))),
+            iced::widget::text(match example {
+                Some(example) => format!("e.g. {example}"),
+                None => "Add a frequency and a collection mode to see an example".to_string(),
+            })
+            .size(12),
+        ]
+        .spacing(4);
+        iced::widget::column![settings, template].spacing(10).into()
+    }
+
+    /// Start, stop and step inputs generating `list`, with why the range
+    /// can't be generated when it can't
+    fn range_widget(&self, list: ValueList) -> iced::Element<'_, Message> {
+        let draft = &self.range_drafts[list as usize];
+        let values = draft.values();
+        let input = |placeholder, field: RangeField, text: &str| {
+            let valid = text.is_empty() || utils::parse_finite(text).is_some();
+            iced::widget::text_input(placeholder, text)
+                .on_input(move |c| Message::from(CaptureSettingsMsg::RangeInput((list, field, c))))
+                .style(if valid {
+                    iced::widget::text_input::default
+                } else {
+                    validation::invalid_input
+                })
+                .width(70)
+        };
+        iced::widget::column![
+            iced::widget::row![
+                input("from", RangeField::Start, &draft.start),
+                input("to", RangeField::Stop, &draft.stop),
+                input("step", RangeField::Step, &draft.step),
+            ]
+            .spacing(4),
+            iced::widget::row![
+                iced::widget::button("Generate Range").on_press_maybe(
+                    matches!(values, Some(Ok(_)))
+                        .then_some(CaptureSettingsMsg::GenerateRange(list).into())
+                ),
+                iced::widget::checkbox("append", draft.append)
+                    .on_toggle(move |c| Message::from(CaptureSettingsMsg::RangeAppend((list, c)))),
+            ]
+            .spacing(10),
+        ]
+        .push_maybe(match values {
+            Some(Err(e)) => Some(
+                iced::widget::text(e)
+                    .size(12)
+                    .style(iced::widget::text::danger),
+            ),
+            _ => None,
+        })
+        .spacing(4)
         .into()
     }
 
-    pub fn sample_rate_widget(&self, id: usize) -> iced::Element<Message> {
+    /// `duplicate` marks a value repeated elsewhere in the list, and rates
+    /// `profile` can't sample at are outlined in red
+    pub fn sample_rate_widget(
+        &self,
+        id: usize,
+        duplicate: bool,
+        profile: Option<&HardwareProfile>,
+    ) -> iced::Element<'_, Message> {
+        let rate = self.sample_rates[id];
+        let unsupported = rate <= 0.0 || profile.is_some_and(|x| !x.samples_at(rate));
         iced::widget::row![
             iced::widget::button("Remove")
                 .on_press(Message::from(CaptureSettingsMsg::DelSampleRate(id))),
-            iced::widget::text_input("0", &format!("{}", self.sample_rates[id]))
-                .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateSampleRate((c, id)))),
+            validation::number_input(
+                "0",
+                self.sample_rates[id],
+                self.input(ValueList::SampleRates, id),
+                if unsupported {
+                    validation::invalid_input
+                } else {
+                    duplicate_style(duplicate)
+                },
+            )
+            .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateSampleRate((c, id)))),
         ]
         .into()
     }
-    pub fn duration_widget(&self, id: usize) -> iced::Element<Message> {
+    pub fn duration_widget(&self, id: usize, duplicate: bool) -> iced::Element<'_, Message> {
         iced::widget::row![
             iced::widget::button("Remove")
                 .on_press(Message::from(CaptureSettingsMsg::DelDuration(id))),
-            iced::widget::text_input("0", &format!("{}", self.durations[id]))
-                .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateDuration((c, id)))),
+            validation::number_input(
+                "0",
+                self.durations[id],
+                self.input(ValueList::Durations, id),
+                duplicate_style(duplicate)
+            )
+            .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateDuration((c, id)))),
         ]
         .into()
     }
-    pub fn gains_widget(&self, id: usize) -> iced::Element<Message> {
+    /// The +/- buttons step by the profile's gain step
+    pub fn gains_widget(
+        &self,
+        id: usize,
+        duplicate: bool,
+        profile: Option<&HardwareProfile>,
+    ) -> iced::Element<'_, Message> {
         iced::widget::row![
             iced::widget::button("Remove").on_press(Message::from(CaptureSettingsMsg::DelGain(id))),
-            iced::widget::text_input("0", &format!("{}", self.gains[id]))
-                .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateGain((c, id)))),
+            iced::widget::button("-").on_press(Message::from(CaptureSettingsMsg::StepGain((
+                id,
+                device::gain_delta(profile, -1.0),
+            )))),
+            validation::number_input(
+                "0",
+                self.gains[id],
+                self.input(ValueList::Gains, id),
+                duplicate_style(duplicate)
+            )
+            .on_input(move |c| Message::from(CaptureSettingsMsg::UpdateGain((c, id)))),
+            iced::widget::button("+").on_press(Message::from(CaptureSettingsMsg::StepGain((
+                id,
+                device::gain_delta(profile, 1.0),
+            )))),
         ]
         .into()
     }
 }
+
+fn duplicate_style(
+    duplicate: bool,
+) -> fn(&iced::Theme, iced::widget::text_input::Status) -> iced::widget::text_input::Style {
+    if duplicate {
+        validation::warning_input
+    } else {
+        iced::widget::text_input::default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(value: serde_json::Value) -> CaptureSettings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn preset() -> CaptureSettings {
+        settings(serde_json::json!({
+            "sample_rates": [1e6, 2e6], "durations": [60], "gains": [20],
+            "compression": "gzip", "compression_level": 6, "datatype": "ci16",
+            "filename_template": "{hz}.dat", "future": 1,
+        }))
+    }
+
+    #[test]
+    fn applying_replaces_only_what_presets_hold() {
+        let mut current = settings(serde_json::json!({
+            "sample_rates": [5e6], "durations": [10], "gains": [40],
+            "compression": "zstd", "compression_level": 3, "datatype": "cu8",
+            "filename_template": "{name}.dat", "kept": true,
+        }));
+        current.apply(preset(), false);
+        assert_eq!(current.sample_rates, [1e6, 2e6]);
+        assert_eq!(
+            (current.compression.as_str(), current.compression_level),
+            ("gzip", 6)
+        );
+        assert_eq!(current.datatype, "ci16");
+        assert_eq!(current.filename_template(), "{name}.dat");
+        assert!(current.unknown.contains_key("kept") && !current.unknown.contains_key("future"));
+    }
+
+    #[test]
+    fn compression_level_outside_range_is_an_error() {
+        for (level, ok) in [(0, false), (1, true), (22, true), (23, false)] {
+            let zstd = settings(serde_json::json!({
+                "sample_rates": [], "durations": [], "gains": [],
+                "compression": "zstd", "compression_level": level,
+            }));
+            let errors = zstd
+                .validate()
+                .into_iter()
+                .filter(|x| x.severity == validation::Severity::Error)
+                .count();
+            assert_eq!(errors == 0, ok, "level {level}");
+            assert_eq!(
+                zstd.compression_level_issue().is_none(),
+                ok,
+                "level {level}"
+            );
+        }
+    }
+
+    #[test]
+    fn sample_rate_of_zero_or_less_is_an_error() {
+        for (rate, ok) in [(0.0, false), (-2e6, false), (2e6, true)] {
+            let errors: Vec<ValidationIssue> = settings(serde_json::json!({
+                "sample_rates": [1e6, rate], "durations": [], "gains": [],
+                "compression": "", "compression_level": 0,
+            }))
+            .validate()
+            .into_iter()
+            .filter(|x| x.severity == validation::Severity::Error)
+            .collect();
+            assert_eq!(errors.is_empty(), ok, "rate {rate}: {errors:?}");
+            if !ok {
+                assert!(errors[0].message.contains("sample rate 2"), "{errors:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn merging_fills_in_unset_values() {
+        let mut current = settings(serde_json::json!({
+            "sample_rates": [2e6, 5e6], "durations": [], "gains": [],
+            "compression": "", "compression_level": 0,
+        }));
+        current.apply(preset(), true);
+        assert_eq!(current.sample_rates, [2e6, 5e6, 1e6]);
+        assert_eq!(current.compression, "gzip");
+        assert_eq!(current.datatype, "ci16");
+
+        let mut current = settings(serde_json::json!({
+            "sample_rates": [], "durations": [], "gains": [],
+            "compression": "zstd", "compression_level": 3, "datatype": "cu8",
+        }));
+        current.apply(preset(), true);
+        assert_eq!(
+            (current.compression.as_str(), current.datatype.as_str()),
+            ("zstd", "cu8")
+        );
+    }
+}
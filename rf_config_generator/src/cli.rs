@@ -0,0 +1,185 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use crate::{config::Config, file_format::FileFormat, migrate, validation::Severity};
+
+/// Headless transformations reading a config from stdin and writing the
+/// result to stdout
+#[derive(Debug, Clone, Copy)]
+pub enum Transform {
+    /// Every capture task, as JSON
+    ExpandTasks,
+    /// The config itself, as YAML
+    ToYaml,
+}
+
+/// Reads the config at `path`, in the format its extension names, or as
+/// JSON from stdin when `path` is `-`
+fn read_config(path: &Path) -> Result<Config, String> {
+    let bytes = if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+    } else {
+        std::fs::read(path)
+    }
+    .map_err(|e| e.to_string())?;
+    let raw = FileFormat::from_path(path)
+        .read(&bytes)
+        .map_err(|e| e.to_string())?;
+    migrate::read(raw).map(|(config, _)| config)
+}
+
+/// Validates the config at `path` (`-` for stdin) without opening the
+/// window, printing the result as JSON on stdout. See [`validation_report`]
+/// for the exit codes.
+pub fn validate(path: &Path) -> ExitCode {
+    let (code, report) = validation_report(path);
+    println!("{report:#}");
+    code
+}
+
+/// Exit code and JSON report of validating the config at `path`. The code
+/// is 0 when there are no errors (warnings are allowed), 1 when there are
+/// errors and 2 when the file can't be read as a config.
+fn validation_report(path: &Path) -> (ExitCode, serde_json::Value) {
+    let config = match read_config(path) {
+        Ok(config) => config,
+        Err(e) => {
+            return (
+                ExitCode::from(2),
+                serde_json::json!({ "file": path, "error": e }),
+            );
+        }
+    };
+    let issues = config.validate();
+    let valid = !issues.iter().any(|x| x.severity == Severity::Error);
+    let report = serde_json::json!({ "file": path, "valid": valid, "issues": issues });
+    (
+        if valid {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(1)
+        },
+        report,
+    )
+}
+
+/// Prints the JSON schema of the config file, derived from the same types
+/// that read and write it
+pub fn print_schema() -> ExitCode {
+    let schema = schemars::schema_for!(Config);
+    match serde_json::to_string_pretty(&schema) {
+        Ok(schema) => print(&schema),
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Applies `transform` to the config on stdin. Problems go to stderr so
+/// stdout only ever carries the result; exits with 2 on failure.
+pub fn filter(transform: Transform) -> ExitCode {
+    let output = read_config(Path::new("-")).and_then(|config| match transform {
+        Transform::ExpandTasks => {
+            serde_json::to_string_pretty(&config.expand_tasks()).map_err(|e| e.to_string())
+        }
+        Transform::ToYaml => serde_yaml::to_string(&config).map_err(|e| e.to_string()),
+    });
+    match output {
+        Ok(output) => print(&output),
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Writes a command's result to stdout
+fn print(output: &str) -> ExitCode {
+    // A reader that stops early (e.g. `head`) closes the pipe; that's not an error
+    match writeln!(std::io::stdout().lock(), "{}", output.trim_end()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Report on `contents` saved as `name` in a fresh directory
+    fn report(name: &str, contents: &str) -> (ExitCode, serde_json::Value) {
+        let dir =
+            std::env::temp_dir().join(format!("rf_config_generator_{:08x}", rand::random::<u32>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        let report = validation_report(&path);
+        std::fs::remove_dir_all(dir).unwrap();
+        report
+    }
+
+    fn config(frequencies: &str) -> String {
+        format!(
+            r#"{{
+                "frequencies": {frequencies},
+                "capture_settings": {{"sample_rates": [1e6], "durations": [60], "gains": [30], "compression": "", "compression_level": 0}},
+                "collection_modes": {{"survey": {{"sample_rate": 1e6, "duration": 60, "gain": 30}}}},
+                "scheduling": {{"time_slots": []}}
+            }}"#
+        )
+    }
+
+    fn severities(report: &serde_json::Value) -> Vec<&str> {
+        report["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["severity"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn valid_config_exits_0() {
+        let (code, report) = report(
+            "valid.json",
+            &config(r#"{"ism": [{"name": "a", "freq": 433.92e6, "description": ""}]}"#),
+        );
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(report["valid"], true);
+        assert!(severities(&report).is_empty(), "{report}");
+    }
+
+    #[test]
+    fn warnings_alone_exit_0() {
+        let (code, report) = report("warned.json", &config(r#"{"ism": []}"#));
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(report["valid"], true);
+        assert_eq!(severities(&report), ["warning"]);
+    }
+
+    #[test]
+    fn errors_exit_1() {
+        let range = r#"{"ism": [{"name": "a", "freq": 433.92e6, "description": "", "range": {"stop": 1e6, "step": 1e3}}]}"#;
+        let (code, report) = report("invalid.json", &config(range));
+        assert_eq!(code, ExitCode::from(1));
+        assert_eq!(report["valid"], false);
+        assert!(severities(&report).contains(&"error"));
+    }
+
+    #[test]
+    fn unreadable_file_exits_2() {
+        let (code, report) = report("broken.json", "{ not json");
+        assert_eq!(code, ExitCode::from(2));
+        assert!(report["error"].is_string());
+        let (code, _) = validation_report(Path::new("/nonexistent/config.json"));
+        assert_eq!(code, ExitCode::from(2));
+    }
+}
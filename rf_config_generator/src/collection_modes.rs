@@ -1,8 +1,10 @@
-use std::collections::{HashMap, hash_map};
+use std::collections::{HashMap, HashSet, hash_map};
 
 use crate::{
     Message,
+    focus::{self, Field},
     utils::{self, TimeSortedContainer},
+    validation::{self, ValidationIssue},
 };
 
 #[derive(Debug, Clone)]
@@ -10,81 +12,370 @@ pub enum CollectionModesMsg {
     Change((String, CollectionMsg)),
     Add,
     Delete(String),
+    // Copies a mode into a new one placed right after it
+    Duplicate(String),
+    MoveUp(String),
+    MoveDown(String),
+    Toggle(String),
+    ExpandAll,
+    CollapseAll,
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(transparent)]
 pub struct CollectionModes {
+    #[serde(
+        serialize_with = "utils::serialize_in_order",
+        deserialize_with = "utils::deserialize_numbered"
+    )]
     map: HashMap<String, TimeSortedContainer<CollectionMode>>,
+    // Modes whose settings are hidden in the view
+    #[serde(skip)]
+    collapsed: HashSet<String>,
 }
 
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
-struct CollectionMode {
-    sample_rate: f64,
-    duration: f64,
-    gain: f64,
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct CollectionMode {
+    pub sample_rate: f64,
+    pub duration: f64,
+    pub gain: f64,
+    // None leaves the choice of port to the SDR driver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub antenna: Option<String>,
+    // When set, replaces `gain` with every step of the sweep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gain_sweep: Option<GainSweep>,
+    // Text last typed into the sweep's start, stop and step, which may
+    // not parse
+    #[serde(skip)]
+    sweep_inputs: [Option<String>; 3],
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Compares the modes, not which are collapsed
+impl PartialEq for CollectionModes {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+/// Values Capture Settings declares, which modes pick theirs from
+#[derive(Debug, Clone, Copy)]
+pub struct Declared<'a> {
+    pub sample_rates: &'a [f64],
+    pub durations: &'a [f64],
+    pub gains: &'a [f64],
+}
+
+/// Picks a mode value from the declared ones. A loaded value Capture
+/// Settings doesn't declare is still shown, with a warning beside it.
+fn choice<'a>(
+    label: &'a str,
+    value: f64,
+    values: &[f64],
+    on_pick: fn(f64) -> CollectionMsg,
+) -> iced::Element<'a, CollectionMsg> {
+    let declared = values.contains(&value);
+    iced::widget::row![
+        iced::widget::text(label),
+        iced::widget::pick_list(
+            values.to_vec(),
+            (declared || value != 0.0).then_some(value),
+            on_pick
+        )
+        .placeholder(if values.is_empty() {
+            "none in Capture Settings"
+        } else {
+            "choose"
+        }),
+    ]
+    .push_maybe(
+        (!declared && value != 0.0)
+            .then(|| iced::widget::text("not in Capture Settings").style(validation::warning)),
+    )
+    .spacing(10)
+    .into()
+}
+
+/// Inclusive range of gains captured one after another
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct GainSweep {
+    pub start: f64,
+    pub stop: f64,
+    pub step: f64,
+}
+
+impl GainSweep {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.step <= 0.0 {
+            return Err("step must be greater than 0".to_string());
+        }
+        if self.stop < self.start {
+            return Err("stop must not be less than start".to_string());
+        }
+        Ok(())
+    }
+
+    /// Length of [`GainSweep::steps`], worked out without building it
+    pub fn count(&self) -> usize {
+        if self.validate().is_err() {
+            return 0;
+        }
+        (((self.stop - self.start) / self.step + 1e-9).floor() as usize).saturating_add(1)
+    }
+
+    /// Every gain in the sweep, or nothing if the sweep is invalid
+    pub fn steps(&self) -> Vec<f64> {
+        // Computed from the index rather than accumulated so rounding
+        // error can't drop the final step
+        (0..self.count())
+            .map(|i| self.start + i as f64 * self.step)
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
-enum CollectionMsg {
+#[allow(clippy::enum_variant_names)]
+pub enum CollectionMsg {
     ChangeName(String),
-    ChangeSampleRate(String),
-    ChangeDuration(String),
-    ChangeGain(String),
+    ChangeSampleRate(f64),
+    ChangeDuration(f64),
+    ChangeGain(f64),
+    ChangeAntenna(Option<String>),
+    ToggleGainSweep(bool),
+    ChangeSweepStart(String),
+    ChangeSweepStop(String),
+    ChangeSweepStep(String),
 }
 
 impl CollectionMode {
+    /// Gains this mode captures at, one task per entry
+    pub fn gains(&self) -> Vec<f64> {
+        match &self.gain_sweep {
+            Some(sweep) => sweep.steps(),
+            None => vec![self.gain],
+        }
+    }
+
+    /// Length of [`CollectionMode::gains`], worked out without building it
+    pub fn gain_count(&self) -> usize {
+        self.gain_sweep.map_or(1, |x| x.count())
+    }
+
+    /// Names of the values this mode uses that Capture Settings doesn't
+    /// declare. Unset (0) values and swept gains aren't checked.
+    pub fn undeclared(&self, declared: Declared) -> Vec<&'static str> {
+        let gain = if self.gain_sweep.is_some() {
+            0.0
+        } else {
+            self.gain
+        };
+        [
+            ("sample rate", self.sample_rate, declared.sample_rates),
+            ("duration", self.duration, declared.durations),
+            ("gain", gain, declared.gains),
+        ]
+        .into_iter()
+        .filter(|(_, value, values)| *value != 0.0 && !values.contains(value))
+        .map(|(label, _, _)| label)
+        .collect()
+    }
+
     pub fn update(&mut self, msg: CollectionMsg) {
         match msg {
-            CollectionMsg::ChangeSampleRate(c) => {
-                if let Ok(rate) = c.parse() {
-                    self.sample_rate = rate;
+            CollectionMsg::ChangeSampleRate(rate) => {
+                self.sample_rate = rate;
+            }
+            CollectionMsg::ChangeDuration(duration) => {
+                self.duration = duration;
+            }
+            CollectionMsg::ChangeGain(gain) => {
+                self.gain = gain;
+            }
+            CollectionMsg::ChangeAntenna(antenna) => {
+                self.antenna = antenna;
+            }
+            CollectionMsg::ToggleGainSweep(enabled) => {
+                self.gain_sweep = enabled.then_some(GainSweep {
+                    start: self.gain,
+                    stop: self.gain,
+                    step: 1.0,
+                });
+                self.sweep_inputs = Default::default();
+            }
+            CollectionMsg::ChangeSweepStart(c) => {
+                if let (Some(sweep), Some(start)) = (&mut self.gain_sweep, utils::parse_finite(&c))
+                {
+                    sweep.start = start;
                 }
+                self.sweep_inputs[0] = Some(c);
             }
-            CollectionMsg::ChangeDuration(c) => {
-                if let Ok(duration) = c.parse() {
-                    self.duration = duration;
+            CollectionMsg::ChangeSweepStop(c) => {
+                if let (Some(sweep), Some(stop)) = (&mut self.gain_sweep, utils::parse_finite(&c)) {
+                    sweep.stop = stop;
                 }
+                self.sweep_inputs[1] = Some(c);
             }
-            CollectionMsg::ChangeGain(c) => {
-                if let Ok(gain) = c.parse() {
-                    self.gain = gain;
+            CollectionMsg::ChangeSweepStep(c) => {
+                if let (Some(sweep), Some(step)) = (&mut self.gain_sweep, utils::parse_finite(&c)) {
+                    sweep.step = step;
                 }
+                self.sweep_inputs[2] = Some(c);
             }
             _ => (),
         }
     }
 
-    pub fn view(&self) -> iced::widget::Column<'_, CollectionMsg> {
+    fn gain_view<'a>(&'a self, gains: &[f64]) -> iced::Element<'a, CollectionMsg> {
+        let Some(sweep) = &self.gain_sweep else {
+            return choice("gain", self.gain, gains, CollectionMsg::ChangeGain);
+        };
+        let status = match sweep.validate() {
+            Ok(()) => iced::widget::text(format!("{} steps", sweep.steps().len())),
+            Err(e) => iced::widget::text(e).style(iced::widget::text::danger),
+        };
+        let input = |placeholder, value, i: usize| {
+            validation::number_input(
+                placeholder,
+                value,
+                self.sweep_inputs[i].as_deref(),
+                iced::widget::text_input::default,
+            )
+        };
         iced::widget::column![
             iced::widget::row![
-                iced::widget::text("sample_rate"),
-                iced::widget::text_input("sample_rate", &format!("{}", self.sample_rate))
-                .on_input(|c| { CollectionMsg::ChangeSampleRate(c) }),
+                iced::widget::text("gain start"),
+                input("start", sweep.start, 0).on_input(CollectionMsg::ChangeSweepStart),
             ],
             iced::widget::row![
-                iced::widget::text("duration"),
-                iced::widget::text_input("duration", &format!("{}", self.duration))
-                .on_input(|c| { CollectionMsg::ChangeDuration(c) }),
+                iced::widget::text("gain stop"),
+                input("stop", sweep.stop, 1).on_input(CollectionMsg::ChangeSweepStop),
             ],
             iced::widget::row![
-                iced::widget::text("gain"),
-                iced::widget::text_input("gain", &format!("{}", self.gain))
-                .on_input(|c| { CollectionMsg::ChangeGain(c) }),
+                iced::widget::text("gain step"),
+                input("step", sweep.step, 2).on_input(CollectionMsg::ChangeSweepStep),
             ],
+            status,
         ]
         .into()
     }
-}
 
+    /// Sample rate, duration and gain are picked from `declared`
+    pub fn view<'a>(
+        &'a self,
+        ports: &[&str],
+        declared: Declared,
+    ) -> iced::widget::Column<'a, CollectionMsg> {
+        iced::widget::column![
+            choice(
+                "sample_rate",
+                self.sample_rate,
+                declared.sample_rates,
+                CollectionMsg::ChangeSampleRate
+            ),
+            choice(
+                "duration",
+                self.duration,
+                declared.durations,
+                CollectionMsg::ChangeDuration
+            ),
+            iced::widget::checkbox("gain sweep", self.gain_sweep.is_some())
+                .on_toggle(CollectionMsg::ToggleGainSweep),
+            self.gain_view(declared.gains),
+            iced::widget::row![
+                iced::widget::text("antenna"),
+                iced::widget::pick_list(
+                    ports.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+                    self.antenna.clone(),
+                    |c| CollectionMsg::ChangeAntenna(Some(c)),
+                )
+                .placeholder("unspecified"),
+                iced::widget::button("Clear").on_press(CollectionMsg::ChangeAntenna(None)),
+            ],
+        ]
+    }
+}
 
 impl CollectionModes {
-    pub fn update(&mut self, msg: CollectionModesMsg) {
+    /// Modes in display order
+    pub fn sorted(&self) -> Vec<(&String, &CollectionMode)> {
+        utils::sorted(&self.map)
+            .into_iter()
+            .map(|(k, v)| (k, &v.val))
+            .collect()
+    }
+
+    fn sorted_keys(&self) -> Vec<String> {
+        self.sorted().into_iter().map(|(k, _)| k.clone()).collect()
+    }
+
+    fn move_by(&mut self, key: &str, up: bool) {
+        let keys = self.sorted_keys();
+        let Some(from) = keys.iter().position(|x| x == key) else {
+            return;
+        };
+        let to = if up {
+            from.checked_sub(1)
+        } else {
+            Some(from + 1)
+        };
+        if let Some(to) = to {
+            utils::reorder(&mut self.map, keys, from, to);
+        }
+    }
+
+    /// `declared` are the values Capture Settings lists for modes to use
+    pub fn validate(&self, declared: Declared) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for (key, mode) in self.sorted() {
+            issues.extend(mode.undeclared(declared).into_iter().map(|label| {
+                ValidationIssue::warning(
+                    "Collection Modes",
+                    format!("'{key}' {label} is not one listed in Capture Settings"),
+                )
+            }));
+            issues.extend(validation::file_name_issue("Collection Modes", "mode", key));
+            if mode.sample_rate <= 0.0 {
+                issues.push(ValidationIssue::error(
+                    "Collection Modes",
+                    format!("'{key}' sample rate must be greater than 0"),
+                ));
+            }
+            if let Some(Err(err)) = mode.gain_sweep.map(|x| x.validate()) {
+                issues.push(ValidationIssue::error(
+                    "Collection Modes",
+                    format!("'{key}' gain sweep: {err}"),
+                ));
+            }
+        }
+        issues
+    }
+
+    /// Ids of the section's fields in Tab order
+    pub fn focus_chain(&self, order: &[Field]) -> Vec<iced::widget::text_input::Id> {
+        let mut chain = Vec::new();
+        // Sample rate, duration and gain are pick lists, which Tab skips
+        for (key, _) in self.sorted() {
+            chain.extend(focus::row(order, &[Field::ModeName], key));
+        }
+        chain
+    }
+
+    pub fn update(&mut self, msg: CollectionModesMsg) -> iced::Task<Message> {
         match msg {
             CollectionModesMsg::Change((key, v)) => {
                 if let CollectionMsg::ChangeName(val) = v {
                     if let hash_map::Entry::Occupied(entry) = self.map.entry(key.to_string()) {
                         let (_, v) = entry.remove_entry();
+                        if self.collapsed.remove(&key) {
+                            self.collapsed.insert(val.clone());
+                        }
                         self.map.insert(val, v);
                     }
                 } else {
@@ -94,36 +385,143 @@ impl CollectionModes {
 
             CollectionModesMsg::Add => {
                 let name = utils::rand_name(|v| self.map.contains_key(v));
-                self.map.insert(name, TimeSortedContainer::default());
+                let order = utils::next_order(&self.map);
+                let id = focus::id(Field::ModeName, &name);
+                self.map.insert(
+                    name,
+                    TimeSortedContainer {
+                        order,
+                        ..Default::default()
+                    },
+                );
+                return focus::focus_and_select(id);
             }
 
             CollectionModesMsg::Delete(key) => {
                 self.map.remove(&key);
+                self.collapsed.remove(&key);
+            }
+
+            CollectionModesMsg::Duplicate(key) => {
+                let Some(mode) = self.map.get(&key).map(|x| x.val.clone()) else {
+                    return iced::Task::none();
+                };
+                let name = (1..)
+                    .map(|i| {
+                        if i == 1 {
+                            format!("{key}_copy")
+                        } else {
+                            format!("{key}_copy_{i}")
+                        }
+                    })
+                    .find(|x| !self.map.contains_key(x))
+                    .unwrap_or_default();
+                let order = utils::next_order(&self.map);
+                self.map
+                    .insert(name.clone(), TimeSortedContainer { val: mode, order });
+                let keys = self.sorted_keys();
+                if let Some(from) = keys.iter().position(|x| *x == name) {
+                    let to = keys.iter().position(|x| *x == key).map_or(from, |x| x + 1);
+                    utils::reorder(&mut self.map, keys, from, to);
+                }
+                let id = focus::id(Field::ModeName, &name);
+                return focus::focus_and_select(id);
+            }
+
+            CollectionModesMsg::MoveUp(key) => self.move_by(&key, true),
+
+            CollectionModesMsg::MoveDown(key) => self.move_by(&key, false),
+
+            CollectionModesMsg::Toggle(key) => {
+                if !self.collapsed.remove(&key) {
+                    self.collapsed.insert(key);
+                }
+            }
+
+            CollectionModesMsg::ExpandAll => {
+                self.collapsed.clear();
+            }
+
+            CollectionModesMsg::CollapseAll => {
+                self.collapsed = self.map.keys().cloned().collect();
             }
         }
+        iced::Task::none()
     }
 
-    pub fn view(&self) -> iced::Element<'_, Message> {
-        let mut sorted: Vec<(&String, &TimeSortedContainer<_>)> = self.map.iter().collect();
-        sorted.sort_by(|x, y| x.1.cmp(y.1));
+    /// `ports` are the antenna ports offered for each mode, `declared` the
+    /// values its sample rate, duration and gain are picked from
+    pub fn view<'a>(&'a self, ports: &[&str], declared: Declared) -> iced::Element<'a, Message> {
+        let sorted = self.sorted();
 
         let top_row = iced::widget::row![
-            iced::widget::button("Add Collection Mode").on_press(CollectionModesMsg::Add),
+            iced::widget::column![
+                iced::widget::button("Add Collection Mode").on_press(CollectionModesMsg::Add),
+                iced::widget::button("Expand All").on_press(CollectionModesMsg::ExpandAll),
+                iced::widget::button("Collapse All").on_press(CollectionModesMsg::CollapseAll),
+            ]
+            .spacing(4),
         ]
         .extend(sorted.into_iter().map(|x| {
+            let collapsed = self.collapsed.contains(x.0);
             iced::widget::row![
+                iced::widget::column![
+                    iced::widget::button(if collapsed { "Expand" } else { "Collapse" })
+                        .on_press(CollectionModesMsg::Toggle(x.0.to_string())),
+                    iced::widget::button("Delete")
+                        .on_press(CollectionModesMsg::Delete(x.0.to_string())),
+                    iced::widget::button("Duplicate")
+                        .on_press(CollectionModesMsg::Duplicate(x.0.to_string())),
+                    iced::widget::button("Up")
+                        .on_press(CollectionModesMsg::MoveUp(x.0.to_string())),
+                    iced::widget::button("Down")
+                        .on_press(CollectionModesMsg::MoveDown(x.0.to_string())),
+                ]
+                .spacing(4),
                 iced::Element::from(
-                    iced::widget::button("Delete").on_press(CollectionModesMsg::Delete(x.0.to_string()))
-                ),
-                iced::Element::from(iced::widget::column![
-                    iced::widget::text_input("collection_name", &x.0)
-                        .on_input(|c| CollectionMsg::ChangeName(c)),
-                        x.1.val.view()
-                ])
+                    iced::widget::column![
+                        iced::widget::text_input("collection_name", x.0)
+                            .id(focus::id(Field::ModeName, x.0))
+                            .on_input(CollectionMsg::ChangeName),
+                    ]
+                    .push_maybe((!collapsed).then(|| x.1.view(ports, declared)))
+                )
                 .map(|msg| CollectionModesMsg::Change((x.0.to_string(), msg))),
             ]
             .into()
         }));
-        iced::Element::from(top_row).map(|msg| Message::CollectionModes(msg))
+        iced::Element::from(top_row).map(Message::CollectionModes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_rate_of_zero_or_less_is_an_error() {
+        for (rate, ok) in [(0.0, false), (-2e6, false), (2e6, true)] {
+            let modes: CollectionModes = serde_json::from_value(serde_json::json!({
+                "survey": {"sample_rate": rate, "duration": 10, "gain": 30},
+            }))
+            .unwrap();
+            let declared = Declared {
+                sample_rates: &[rate],
+                durations: &[10.0],
+                gains: &[30.0],
+            };
+            let issues = modes.validate(declared);
+            let errors: Vec<&ValidationIssue> = issues
+                .iter()
+                .filter(|x| x.severity == validation::Severity::Error)
+                .collect();
+            assert_eq!(errors.is_empty(), ok, "rate {rate}: {errors:?}");
+            if !ok {
+                assert_eq!(
+                    errors[0].message,
+                    "'survey' sample rate must be greater than 0"
+                );
+            }
+        }
     }
 }
@@ -1,44 +1,917 @@
-use crate::{capture_settings, collection_modes, frequency, scheduling, Message};
+use std::collections::BTreeMap;
 
+use crate::{
+    Message, capture_settings, collection_modes, device, focus, frequency, grc, help, scheduling,
+    script, sigmf, tasks, validation,
+};
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Version of the file format this build writes. Files from a newer
+/// version open read-only, since this build can't know what their new
+/// fields mean; see [`Config::is_newer`].
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Capture counts above this get a warning, since such a config tends to
+/// run far longer than whoever wrote it expected
+const MANY_CAPTURES: usize = 1_000;
+
+#[derive(
+    Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct Config {
+    // Format version the file was written in, 0 for files from before
+    // versions were recorded
+    #[serde(default)]
+    version: u32,
     frequencies: frequency::FrequencyConfig,
     capture_settings: capture_settings::CaptureSettings,
     collection_modes: collection_modes::CollectionModes,
     scheduling: scheduling::Scheduling,
+    // Capture priority per frequency group, higher first. Kept out of the
+    // groups themselves so they stay plain arrays; groups left out are 0.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    group_priorities: BTreeMap<String, u8>,
+    // Name of the selected hardware profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+    // RFC3339 timestamps, only ever set by saving
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    // What the config is for, in the user's words
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Config {
-    pub fn update(&mut self, msg: crate::Message) {
-
+    pub fn update(&mut self, msg: crate::Message) -> iced::Task<Message> {
         match msg {
+            crate::Message::Frequency(frequency::FrequencyMessage::Priority((key, priority))) => {
+                match priority {
+                    0 => self.group_priorities.remove(&key),
+                    _ => self.group_priorities.insert(key, priority),
+                };
+            }
+
             crate::Message::Frequency(f) => {
-                self.frequencies.update(Message::Frequency(f));
-            },
+                // Schedule slots and priorities follow their group through a rename
+                if let frequency::FrequencyMessage::GroupNameUpdated((key, new_key)) = &f {
+                    self.scheduling.retarget_group(key, Some(new_key));
+                    if let Some(priority) = self.group_priorities.remove(key) {
+                        self.group_priorities.insert(new_key.clone(), priority);
+                    }
+                }
+                let task = self.frequencies.update(Message::Frequency(f));
+                self.prune_priorities();
+                return task;
+            }
+
+            crate::Message::DeleteGroup((key, clear_references)) => {
+                if clear_references {
+                    self.scheduling.retarget_group(&key, None);
+                }
+                let task = self
+                    .frequencies
+                    .update(frequency::FrequencyMessage::DelGroup(key).into());
+                self.prune_priorities();
+                return task;
+            }
+
+            crate::Message::ImportSigmf((name, recording)) => {
+                let freqs = recording
+                    .frequencies
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let mut freq = frequency::Frequency::new(rand::random());
+                        freq.name = match recording.frequencies.len() {
+                            1 => name.clone(),
+                            _ => format!("{name} {}", i + 1),
+                        };
+                        freq.freq = *value;
+                        freq.description = recording.description.clone();
+                        freq
+                    })
+                    .collect();
+                let gains: Vec<f64> = recording.gain.into_iter().collect();
+                self.capture_settings
+                    .add_missing(&[recording.sample_rate], &[], &gains);
+                return self
+                    .frequencies
+                    .update(frequency::FrequencyMessage::InsertGroup((name, freqs)).into());
+            }
 
             crate::Message::CaptureSettingsMsg(f) => {
-                self.capture_settings.update(Message::CaptureSettingsMsg(f));
-            },
+                let profile = self.profile();
+                self.capture_settings
+                    .update(Message::CaptureSettingsMsg(f), profile);
+            }
 
             crate::Message::CollectionModes(f) => {
-                self.collection_modes.update(f);
+                // Schedule slots follow their mode through a rename
+                if let collection_modes::CollectionModesMsg::Change((
+                    key,
+                    collection_modes::CollectionMsg::ChangeName(new_key),
+                )) = &f
+                {
+                    self.scheduling.retarget_mode(key, Some(new_key));
+                }
+                return self.collection_modes.update(f);
             }
 
             crate::Message::Scheduling(f) => {
                 self.scheduling.update(f);
             }
 
-            _ => ()
+            crate::Message::Notes(notes) => {
+                self.notes = notes;
+            }
+
+            crate::Message::Device(device::DeviceMsg::Select(profile)) => {
+                self.device = profile.map(|x| x.name.to_string());
+            }
+
+            crate::Message::Device(device::DeviceMsg::FillDefaults) => {
+                if let Some(profile) = self.profile() {
+                    self.capture_settings.fill_empty(profile);
+                }
+            }
+
+            _ => (),
+        };
+        iced::Task::none()
+    }
+
+    /// Drops the priorities of groups that no longer exist
+    fn prune_priorities(&mut self) {
+        let frequencies = &self.frequencies;
+        self.group_priorities
+            .retain(|key, _| frequencies.group(key).is_some());
+    }
+
+    /// Frequency groups in display order, which schedule slots pick from
+    fn group_names(&self) -> Vec<String> {
+        self.frequencies
+            .sorted()
+            .into_iter()
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    /// Collection modes in display order, which schedule slots and the
+    /// flowgraph export pick from
+    pub fn mode_names(&self) -> Vec<String> {
+        self.collection_modes
+            .sorted()
+            .into_iter()
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    /// Capture priority of the group `key`
+    pub fn priority(&self, key: &str) -> u8 {
+        self.group_priorities.get(key).copied().unwrap_or_default()
+    }
+
+    /// Records a save made at `timestamp`, setting `created` on the first
+    /// one. Files from a newer version keep their version, as their
+    /// unknown fields are written back out unchanged.
+    pub fn mark_saved(&mut self, timestamp: &str) {
+        self.version = self.version.max(FORMAT_VERSION);
+        if self.created.is_none() {
+            self.created = Some(timestamp.to_string());
+        }
+        self.modified = Some(timestamp.to_string());
+    }
+
+    /// Whether the file was written by a newer version of the format
+    pub fn is_newer(&self) -> bool {
+        self.version > FORMAT_VERSION
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn capture_settings(&self) -> &capture_settings::CaptureSettings {
+        &self.capture_settings
+    }
+
+    /// The selected hardware profile, if it is a known one
+    pub fn profile(&self) -> Option<&'static device::HardwareProfile> {
+        self.device.as_deref().and_then(device::find)
+    }
+
+    /// Top-level keys a config file is made of
+    const SECTIONS: &[&str] = &[
+        "frequencies",
+        "capture_settings",
+        "collection_modes",
+        "scheduling",
+    ];
+
+    /// Why a freshly parsed file may not be a config at all, given the
+    /// config it produced and the raw JSON it came from. Files that carry
+    /// none of the sections, or that leave every section empty, parse fine
+    /// but would silently replace the current config with nothing.
+    pub fn suspicion(&self, raw: &serde_json::Value) -> Option<String> {
+        let known = raw
+            .as_object()
+            .is_some_and(|x| Self::SECTIONS.iter().any(|key| x.contains_key(*key)));
+        if !known {
+            return Some("it has none of the sections a config file contains".to_string());
+        }
+        let empty = self.frequencies.sorted().is_empty()
+            && self.capture_settings.is_empty()
+            && self.collection_modes.sorted().is_empty()
+            && self.scheduling.is_empty();
+        empty.then(|| {
+            "it holds no frequencies, capture settings, collection modes or schedule".to_string()
+        })
+    }
+
+    /// When the config was last saved, as RFC3339
+    pub fn modified(&self) -> Option<&str> {
+        self.modified.as_deref()
+    }
+
+    /// What refers to the frequency group `key` by name, and would be left
+    /// pointing at nothing if it were deleted
+    pub fn group_references(&self, key: &str) -> Vec<String> {
+        self.scheduling.group_references(key)
+    }
+
+    /// Frequencies of the group `key`
+    pub fn group(&self, key: &str) -> Option<&[frequency::Frequency]> {
+        self.frequencies.group(key).map(|x| x.vec.as_slice())
+    }
+
+    /// Group that most recently received input, target of the add-frequency shortcut
+    pub fn active_group(&self) -> Option<&String> {
+        self.frequencies.active_group()
+    }
+
+    /// Whether `freq` passes the frequency tag filter
+    pub fn matches_filter(&self, freq: &frequency::Frequency) -> bool {
+        self.frequencies.matches_filter(freq)
+    }
+
+    /// A standalone copy of the config holding only the frequencies
+    /// matching `keep`. Groups with nothing left are pruned, along with
+    /// their schedule slots and priorities.
+    pub fn subset(&self, keep: impl Fn(&frequency::Frequency) -> bool) -> Config {
+        let mut subset = self.clone();
+        subset.frequencies.retain(keep);
+        for key in self.group_names() {
+            if subset.frequencies.group(&key).is_none() {
+                subset.scheduling.drop_group(&key);
+            }
+        }
+        subset.prune_priorities();
+        subset
+    }
+
+    /// Problems across every section, errors first
+    pub fn validate(&self) -> Vec<validation::ValidationIssue> {
+        let mut issues = self.frequencies.validate();
+        issues.extend(self.capture_settings.validate());
+        issues.extend(
+            self.collection_modes
+                .validate(self.capture_settings.declared()),
+        );
+        issues.extend(
+            self.scheduling
+                .validate(&self.group_names(), &self.mode_names()),
+        );
+        issues.extend(self.output_clashes());
+        issues.extend(self.device_issues());
+        let (captures, seconds) = self.capture_count();
+        if captures > MANY_CAPTURES {
+            issues.push(validation::ValidationIssue::warning(
+                "Summary",
+                format!(
+                    "expands to {captures} captures, about {} of recording",
+                    format_seconds(seconds)
+                ),
+            ));
+        }
+        if !self.scheduling.is_empty() && self.collection_modes.sorted().is_empty() {
+            issues.push(validation::ValidationIssue::error(
+                "Scheduling",
+                "there are schedule slots but no collection modes, so nothing would be captured in them",
+            ));
+        }
+        issues.sort_by_key(|x| std::cmp::Reverse(x.severity));
+        issues
+    }
+
+    /// Frequencies and sample rates the selected hardware can't do
+    fn device_issues(&self) -> Vec<validation::ValidationIssue> {
+        let Some(profile) = self.profile() else {
+            return Vec::new();
         };
+        let mut issues = Vec::new();
+        for (key, group) in self.frequencies.sorted() {
+            let untunable = |x: &&frequency::Frequency| {
+                let (low, high) = x.span();
+                !profile.tunes_to(low) || !profile.tunes_to(high)
+            };
+            for freq in group.vec.iter().filter(untunable) {
+                issues.push(validation::ValidationIssue::warning(
+                    "Frequencies",
+                    format!(
+                        "'{}' in '{key}' ({}) is outside the {} tuning range of {}",
+                        freq.name,
+                        frequency::format_hz(freq.freq),
+                        profile.name,
+                        profile.freq_limits()
+                    ),
+                ));
+            }
+        }
+        for rate in self.capture_settings.declared().sample_rates {
+            if *rate > 0.0 && !profile.samples_at(*rate) {
+                issues.push(validation::ValidationIssue::warning(
+                    "Capture Settings",
+                    format!(
+                        "sample rate {rate} is outside the {} range of {}",
+                        profile.name,
+                        profile.sample_rate_limits()
+                    ),
+                ));
+            }
+        }
+        issues
+    }
+
+    /// Tokens the file name template lacks, so that captures differing only
+    /// in what they leave out get the same name and overwrite each other.
+    /// Worked out from what varies between the captures rather than by
+    /// naming each of them. `{timestamp}` is the same for every task of an
+    /// export, so it doesn't tell them apart.
+    fn output_clashes(&self) -> Option<validation::ValidationIssue> {
+        let template = self.capture_settings.filename_template();
+        let uses = |token: &str| template.contains(&format!("{{{token}}}"));
+        let freqs: Vec<(&String, &frequency::Frequency)> = self
+            .frequencies
+            .sorted()
+            .into_iter()
+            .flat_map(|(key, group)| {
+                group
+                    .vec
+                    .iter()
+                    .filter(|x| x.enabled)
+                    .map(move |x| (key, x))
+            })
+            .collect();
+        let modes = self.collection_modes.sorted();
+        let points: usize = freqs.iter().map(|(_, x)| x.point_count()).sum();
+        // Only where an entry starts is compared, so ranges that merely
+        // overlap another group aren't caught
+        let mut first_group = std::collections::HashMap::new();
+        let shared = freqs
+            .iter()
+            .any(|(key, x)| *first_group.entry(x.freq.to_bits()).or_insert(*key) != *key);
+        let missing: Vec<&str> = [
+            (
+                points > 1 && !uses("hz") && !uses("freq_mhz"),
+                "{hz} or {freq_mhz}",
+            ),
+            (modes.len() > 1 && !uses("mode"), "{mode}"),
+            (
+                modes.iter().any(|(_, x)| x.gain_count() > 1) && !uses("gain"),
+                "{gain}",
+            ),
+            (shared && !uses("group"), "{group}"),
+        ]
+        .into_iter()
+        .filter_map(|(missing, token)| missing.then_some(token))
+        .collect();
+        (!missing.is_empty() && self.capture_count().0 > 1).then(|| {
+            validation::ValidationIssue::error(
+                "Capture Settings",
+                format!(
+                    "file name template gives several captures the same name; add {} to tell them apart",
+                    missing.join(", ")
+                ),
+            )
+        })
+    }
+
+    /// Ids of every navigable text field, in the order Tab visits them
+    pub fn focus_chain(&self, order: &[focus::Field]) -> Vec<iced::widget::text_input::Id> {
+        let mut chain = self.frequencies.focus_chain(order);
+        chain.extend(self.collection_modes.focus_chain(order));
+        chain.extend(self.scheduling.focus_chain(order));
+        chain
     }
 
-    pub fn view(&self) -> iced::Element<crate::Message> {
+    /// Every capture the config describes: each frequency under each
+    /// collection mode, once per gain the mode captures at. Groups with a
+    /// higher priority come first, otherwise they keep display order.
+    pub fn expand_tasks(&self) -> Vec<tasks::CaptureTask> {
+        self.tasks().collect()
+    }
+
+    /// The tasks of [`Config::expand_tasks`], built as they are taken
+    fn tasks(&self) -> impl Iterator<Item = tasks::CaptureTask> + '_ {
+        let modes = self.collection_modes.sorted();
+        let mut groups = self.frequencies.sorted();
+        groups.sort_by_key(|(key, _)| std::cmp::Reverse(self.priority(key)));
+        groups.into_iter().flat_map(move |(group, freqs)| {
+            let modes = modes.clone();
+            freqs
+                .vec
+                .iter()
+                .filter(|x| x.enabled)
+                .flat_map(|x| x.points().into_iter().map(move |point| (x, point)))
+                .flat_map(move |(freq, point)| {
+                    modes
+                        .clone()
+                        .into_iter()
+                        .flat_map(move |(mode_name, mode)| {
+                            mode.gains()
+                                .into_iter()
+                                .map(move |gain| tasks::CaptureTask {
+                                    group: group.clone(),
+                                    name: freq.name.clone(),
+                                    freq: point,
+                                    mode: mode_name.to_string(),
+                                    sample_rate: mode.sample_rate,
+                                    duration: mode.duration,
+                                    gain,
+                                    antenna: mode.antenna.clone(),
+                                })
+                        })
+                })
+        })
+    }
+
+    /// Number of tasks [`Config::expand_tasks`] gives and their total
+    /// duration in seconds, counted without building them: every capture
+    /// point of an enabled frequency, under every mode, once per gain
+    pub fn capture_count(&self) -> (usize, f64) {
+        let (points, per_point, seconds) = self.capture_factors();
+        (points * per_point, points as f64 * seconds)
+    }
+
+    /// What [`Config::capture_count`] multiplies: the capture points of
+    /// enabled frequencies, the captures each point gets (one per gain of
+    /// every mode) and how many seconds those take
+    fn capture_factors(&self) -> (usize, usize, f64) {
+        let points: usize = self
+            .frequencies
+            .sorted()
+            .iter()
+            .flat_map(|(_, group)| group.vec.iter().filter(|x| x.enabled))
+            .map(|x| x.point_count())
+            .sum();
+        let (per_point, seconds) = self
+            .collection_modes
+            .sorted()
+            .iter()
+            .map(|(_, mode)| (mode.gain_count(), mode.duration))
+            .fold((0, 0.0), |(count, seconds), (gains, duration)| {
+                (count + gains, seconds + gains as f64 * duration)
+            });
+        (points, per_point, seconds)
+    }
+
+    /// Read-only list of every expanded task, in capture order, with the
+    /// schedule slots each runs in
+    pub fn tasks_view(&self) -> iced::Element<'_, Message> {
+        tasks::view(&self.expand_tasks(), |group| {
+            match self.scheduling.windows(group) {
+                windows if windows.is_empty() => "unscheduled".to_string(),
+                windows => windows.join("; "),
+            }
+        })
+    }
+
+    /// Every expanded task as CSV, with the schedule slots each runs in,
+    /// in the order [`Config::expand_tasks`] gives them. That order is
+    /// saved with the file, so the same file always gives the same rows.
+    pub fn capture_plan(&self) -> String {
+        tasks::csv(&self.expand_tasks(), |group| {
+            match self.scheduling.windows(group) {
+                windows if windows.is_empty() => "unscheduled".to_string(),
+                windows => windows.join("; "),
+            }
+        })
+    }
+
+    /// Every frequency, in display order, for choosing one to generate a
+    /// flowgraph for
+    pub fn frequency_picks(&self) -> Vec<grc::FrequencyPick> {
+        self.frequencies
+            .sorted()
+            .into_iter()
+            .flat_map(|(key, group)| {
+                group
+                    .vec
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, freq)| grc::FrequencyPick::new(key, i, freq))
+            })
+            .collect()
+    }
+
+    /// The picked frequency and the mode named `mode`, or None if either
+    /// no longer exists
+    fn picked(
+        &self,
+        pick: &grc::FrequencyPick,
+        mode: &str,
+    ) -> Option<(
+        &frequency::Frequency,
+        &String,
+        &collection_modes::CollectionMode,
+    )> {
+        let freq = self.group(&pick.group)?.get(pick.index)?;
+        let (mode_name, mode) = self
+            .collection_modes
+            .sorted()
+            .into_iter()
+            .find(|(name, _)| *name == mode)?;
+        Some((freq, mode_name, mode))
+    }
+
+    /// File name and contents of the flowgraph capturing the picked
+    /// frequency with `mode`
+    pub fn flowgraph(&self, pick: &grc::FrequencyPick, mode: &str) -> Option<(String, String)> {
+        let (freq, mode_name, mode) = self.picked(pick, mode)?;
+        let name = crate::utils::sanitize_name(&format!("{}_{mode_name}.grc", freq.name));
+        Some((name, grc::config_to_grc(freq, mode_name, mode)))
+    }
+
+    /// File name and contents of the SigMF metadata of a recording of the
+    /// picked frequency with `mode`
+    pub fn sigmf(&self, pick: &grc::FrequencyPick, mode: &str) -> Option<(String, String)> {
+        let (freq, mode_name, mode) = self.picked(pick, mode)?;
+        let name = crate::utils::sanitize_name(&format!("{}_{mode_name}.sigmf-meta", freq.name));
+        let meta = sigmf::to_sigmf(freq, mode_name, mode, self.capture_settings.datatype());
+        Some((name, serde_json::to_string_pretty(&meta).ok()?))
+    }
+
+    /// Shell script running every expanded task through `command`
+    pub fn capture_script(&self, shell: script::Shell, command: &str) -> String {
+        let (compression, level) = self.capture_settings.compression();
+        script::render(
+            &self.expand_tasks(),
+            shell,
+            command,
+            compression,
+            level,
+            self.capture_settings.filename_template(),
+        )
+    }
+
+    /// How many captures the config expands to, in large type since it
+    /// grows as the product of every list; amber past [`MANY_CAPTURES`]
+    fn capture_count_view(&self) -> iced::Element<'_, crate::Message> {
+        let (captures, seconds) = self.capture_count();
+        let (points, per_point, _) = self.capture_factors();
+        let text = iced::widget::text(format!(
+            "This config expands to {captures} captures ({points} frequencies, each under {per_point} mode and gain combinations), about {} of recording",
+            format_seconds(seconds)
+        ))
+        .size(20);
+        if captures > MANY_CAPTURES {
+            text.style(validation::warning).into()
+        } else {
+            text.into()
+        }
+    }
+
+    fn summary_view(&self) -> iced::Element<'_, crate::Message> {
+        let groups = self.frequencies.sorted();
+        let frequencies: usize = groups.iter().map(|(_, group)| group.vec.len()).sum();
+        iced::widget::column![
+            iced::widget::text(format!(
+                "{} groups, {} frequencies, {} collection modes",
+                groups.len(),
+                frequencies,
+                self.collection_modes.sorted().len(),
+            )),
+            self.capture_count_view(),
+            iced::widget::text(format!(
+                "Created: {}    Modified: {}",
+                self.created.as_deref().unwrap_or("never saved"),
+                self.modified.as_deref().unwrap_or("never saved"),
+            )),
+        ]
+        .push_maybe((!self.group_priorities.is_empty()).then(|| {
+            let mut prioritized: Vec<(&String, &u8)> = self.group_priorities.iter().collect();
+            prioritized.sort_by_key(|(_, priority)| std::cmp::Reverse(**priority));
+            let prioritized: Vec<String> = prioritized
+                .into_iter()
+                .map(|(key, priority)| format!("{key} ({priority})"))
+                .collect();
+            iced::widget::text(format!(
+                "Captured first, by priority: {}",
+                prioritized.join(", ")
+            ))
+        }))
+        .into()
+    }
+
+    /// `presets` are the capture-settings preset controls and `templates`
+    /// the group template controls, each shown with its section. `notes`
+    /// is the editor for the notes, which keeps its own text state. `issues`
+    /// are the results of the last [`Config::validate`], which the app
+    /// recomputes on its own schedule.
+    pub fn view<'a>(
+        &'a self,
+        presets: iced::Element<'a, Message>,
+        templates: iced::Element<'a, Message>,
+        notes: iced::Element<'a, Message>,
+        issues: &'a [validation::ValidationIssue],
+    ) -> iced::Element<'a, Message> {
         iced::widget::column![
-            self.frequencies.view(),
-            self.capture_settings.view(),
-            self.collection_modes.view(),
-            self.scheduling.view(),
-        ].spacing(20).into()
+            help::section(
+                iced::widget::column![self.summary_view(), validation::view(issues)],
+                help::Topic::Summary,
+            ),
+            help::section(
+                iced::widget::column![iced::widget::text("Notes"), notes].spacing(4),
+                help::Topic::Notes,
+            ),
+            help::section(
+                iced::widget::column![
+                    templates,
+                    self.frequencies
+                        .view(&self.group_priorities, self.profile())
+                ]
+                .spacing(10),
+                help::Topic::Frequencies,
+            ),
+            help::section(
+                iced::widget::column![
+                    device::view(self.profile()),
+                    presets,
+                    self.capture_settings.view(
+                        self.profile(),
+                        self.tasks().next().map(|x| x.file_name(
+                            self.capture_settings.filename_template(),
+                            &tasks::timestamp()
+                        )),
+                    ),
+                ]
+                .spacing(10),
+                help::Topic::CaptureSettings,
+            ),
+            help::section(
+                self.collection_modes.view(
+                    device::antenna_ports(self.profile()),
+                    self.capture_settings.declared()
+                ),
+                help::Topic::CollectionModes,
+            ),
+            help::section(
+                self.scheduling.view(self.group_names(), self.mode_names()),
+                help::Topic::Scheduling,
+            ),
+        ]
+        .spacing(20)
+        .into()
+    }
+}
+
+/// Seconds as hours and minutes, or seconds when under a minute
+fn format_seconds(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    match (minutes / 60, minutes % 60) {
+        _ if seconds < 60.0 => format!("{seconds} s"),
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(template: &str) -> Config {
+        serde_json::from_value(serde_json::json!({
+            "frequencies": {
+                "ism": [
+                    {"name": "ism_433", "freq": 433.92e6, "description": ""},
+                    {"name": "ism_868", "freq": 868e6, "description": ""},
+                ],
+                "fm": [{"name": "fm", "freq": 88e6, "description": "", "range": {"stop": 108e6, "step": 0.2e6}}],
+            },
+            "capture_settings": {
+                "sample_rates": [], "durations": [], "gains": [],
+                "compression": "", "compression_level": 0, "filename_template": template,
+            },
+            "collection_modes": {
+                "survey": {"sample_rate": 1e6, "duration": 10, "gain": 30},
+                "detail": {
+                    "sample_rate": 2e6, "duration": 60, "gain": 40,
+                    "gain_sweep": {"start": 10, "stop": 30, "step": 10},
+                },
+            },
+            "scheduling": {"time_slots": []},
+        }))
+        .unwrap()
+    }
+
+    /// Whether opening `raw` would be refused or questioned
+    fn flagged(raw: serde_json::Value) -> bool {
+        !crate::migrate::read(raw).is_ok_and(|(config, raw)| config.suspicion(&raw).is_none())
+    }
+
+    #[test]
+    fn package_json_is_flagged() {
+        let package = serde_json::json!({
+            "name": "rf-dashboard",
+            "version": "1.2.0",
+            "scripts": {"build": "vite build"},
+            "dependencies": {"react": "^18.2.0"},
+        });
+        assert!(flagged(package.clone()));
+        assert!(Config::default().suspicion(&package).is_some());
+        let hollow = serde_json::json!({
+            "frequencies": {},
+            "capture_settings": {"sample_rates": [], "durations": [], "gains": [], "compression": "", "compression_level": 0},
+            "collection_modes": {},
+            "scheduling": {"time_slots": []},
+        });
+        assert!(flagged(hollow));
+    }
+
+    #[test]
+    fn real_config_is_not_flagged() {
+        assert!(!flagged(
+            serde_json::from_str(include_str!("../../updated_config.json")).unwrap()
+        ));
+        let raw = serde_json::to_value(config(tasks::DEFAULT_FILENAME_TEMPLATE)).unwrap();
+        assert!(!flagged(raw));
+    }
+
+    #[test]
+    fn template_telling_captures_apart_has_no_clash() {
+        assert!(
+            config(tasks::DEFAULT_FILENAME_TEMPLATE)
+                .output_clashes()
+                .is_none()
+        );
+        assert!(
+            config("{mode}_{freq_mhz}_{gain}.dat")
+                .output_clashes()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn template_missing_tokens_clashes() {
+        let issue = config("{hz}_{timestamp}.dat").output_clashes().unwrap();
+        assert!(issue.message.contains("{mode}"), "{}", issue.message);
+        let issue = config("{mode}.dat").output_clashes().unwrap();
+        assert!(
+            issue.message.contains("{hz} or {freq_mhz}"),
+            "{}",
+            issue.message
+        );
+    }
+
+    #[test]
+    fn capture_count_multiplies_points_by_mode_gains() {
+        // 2 singles and 101 range points, under one mode and a 3 step sweep
+        let config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        assert_eq!(
+            config.capture_count(),
+            (103 * 4, 103.0 * (10.0 + 3.0 * 60.0))
+        );
+        assert_eq!(config.expand_tasks().len(), 103 * 4);
+    }
+
+    #[test]
+    fn group_and_mode_order_survives_save_and_open() {
+        let mut config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        let mut uhf = frequency::Frequency::new(1);
+        (uhf.name, uhf.freq) = ("uhf_446".to_string(), 446e6);
+        let _ = config.update(
+            frequency::FrequencyMessage::InsertGroup(("uhf".to_string(), vec![uhf])).into(),
+        );
+        assert_eq!(config.group_names(), ["ism", "fm", "uhf"]);
+        // A rotation, which neither a swap nor a reversal produces
+        for _ in 0..2 {
+            let _ =
+                config.update(frequency::FrequencyMessage::MoveGroupDown("ism".to_string()).into());
+        }
+        let _ = config.update(Message::CollectionModes(
+            collection_modes::CollectionModesMsg::MoveDown("survey".to_string()),
+        ));
+        let names = |config: &Config| (config.group_names(), config.mode_names());
+        let before = names(&config);
+        assert_eq!(before.0, ["fm", "uhf", "ism"]);
+        assert_eq!(before.1, ["detail", "survey"]);
+        let saved = serde_json::to_value(&config).unwrap();
+        let (reopened, _) = crate::migrate::read(saved).unwrap();
+        assert_eq!(names(&reopened), before);
+        let written = |config: &Config| {
+            serde_json::to_string(&(&config.frequencies, &config.collection_modes)).unwrap()
+        };
+        assert_eq!(written(&reopened), written(&config));
+    }
+
+    #[test]
+    fn capture_plan_follows_capture_order() {
+        // "ism" is listed first, though "fm" sorts first by name
+        let config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        let plan = config.capture_plan();
+        let groups: Vec<&str> = plan
+            .lines()
+            .skip(1)
+            .filter_map(|x| x.split(',').next())
+            .collect();
+        let expected: Vec<String> = config.expand_tasks().into_iter().map(|x| x.group).collect();
+        assert_eq!(groups, expected);
+        assert_eq!(groups.first(), Some(&"ism"));
+    }
+
+    #[test]
+    fn subset_drops_slots_and_priorities_of_pruned_groups() {
+        let mut config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        config.scheduling = serde_json::from_value(serde_json::json!({"time_slots": [
+            {"name": "night", "start": "01:00", "end": "02:00", "group": "fm", "mode": "survey"},
+            {"name": "day", "start": "12:00", "end": "13:00", "group": "ism", "mode": "survey"},
+        ]}))
+        .unwrap();
+        for key in ["fm", "ism"] {
+            let _ =
+                config.update(frequency::FrequencyMessage::Priority((key.to_string(), 2)).into());
+        }
+        let scheduling = |config: &Config| {
+            config
+                .validate()
+                .into_iter()
+                .filter(|x| x.section == "Scheduling")
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scheduling(&config), []);
+
+        let subset = config.subset(|x| x.name.starts_with("ism"));
+        assert_eq!(subset.group_names(), ["ism"]);
+        assert_eq!(
+            subset.scheduling.group_references("fm"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            subset.scheduling.group_references("ism"),
+            ["schedule slot 'day'"]
+        );
+        assert_eq!(subset.group_priorities.keys().collect::<Vec<_>>(), ["ism"]);
+        assert_eq!(scheduling(&subset), []);
+    }
+
+    #[test]
+    fn schedules_without_modes_are_errors() {
+        let errors = |config: &Config| {
+            config
+                .validate()
+                .into_iter()
+                .filter(|x| x.section == "Scheduling" && x.severity == validation::Severity::Error)
+                .map(|x| x.message)
+                .collect::<Vec<_>>()
+        };
+        let mut config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        config.scheduling = serde_json::from_value(serde_json::json!({"time_slots": [
+            {"name": "night", "start": "01:00", "end": "02:00", "mode": "survey"},
+            {"name": "day", "start": "12:00", "end": "13:00", "mode": "gone"},
+            {"name": "off", "start": "14:00", "end": "15:00", "mode": "gone", "enabled": false},
+        ]}))
+        .unwrap();
+        assert_eq!(
+            errors(&config),
+            ["schedule slot 'day' refers to mode 'gone', which no longer exists"]
+        );
+
+        config.collection_modes = Default::default();
+        let errors = errors(&config);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(
+            errors.iter().any(|x| x.contains("no collection modes")),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn tasks_are_built_lazily_in_expand_order() {
+        let config = config(tasks::DEFAULT_FILENAME_TEMPLATE);
+        assert_eq!(
+            config.tasks().next().as_ref(),
+            config.expand_tasks().first()
+        );
+        assert_eq!(config.expand_tasks().len(), config.capture_count().0);
     }
 }
@@ -0,0 +1,189 @@
+use crate::Message;
+
+/// Antenna ports offered when no hardware profile is selected
+const DEFAULT_ANTENNA_PORTS: &[&str] = &["TX/RX", "RX2", "RX1", "RX", "A", "B"];
+
+/// What an SDR model supports. Drives the values offered in pickers; typed
+/// values outside these lists are still accepted, but ones outside the
+/// tuning or sample rate range are flagged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HardwareProfile {
+    pub name: &'static str,
+    pub antenna_ports: &'static [&'static str],
+    pub sample_rates: &'static [f64],
+    pub gains: &'static [f64],
+    // dB the gain steppers move by; None when the tuner only takes the
+    // listed gains, which the steppers then move between
+    pub gain_step: Option<f64>,
+    // Lowest and highest center frequency the tuner reaches, in Hz
+    pub freq_range: (f64, f64),
+    // Lowest and highest sample rate the hardware delivers, in Hz
+    pub sample_rate_range: (f64, f64),
+}
+
+impl HardwareProfile {
+    pub fn tunes_to(&self, freq: f64) -> bool {
+        (self.freq_range.0..=self.freq_range.1).contains(&freq)
+    }
+
+    pub fn samples_at(&self, rate: f64) -> bool {
+        (self.sample_rate_range.0..=self.sample_rate_range.1).contains(&rate)
+    }
+
+    /// Tuning range, e.g. `24 MHz to 1766 MHz`
+    pub fn freq_limits(&self) -> String {
+        format!(
+            "{} MHz to {} MHz",
+            self.freq_range.0 / 1e6,
+            self.freq_range.1 / 1e6
+        )
+    }
+
+    pub fn sample_rate_limits(&self) -> String {
+        format!(
+            "{} MHz to {} MHz",
+            self.sample_rate_range.0 / 1e6,
+            self.sample_rate_range.1 / 1e6
+        )
+    }
+}
+
+impl std::fmt::Display for HardwareProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub const PROFILES: &[HardwareProfile] = &[
+    HardwareProfile {
+        name: "USRP B200/B210",
+        antenna_ports: &["TX/RX", "RX2"],
+        sample_rates: &[1e6, 2e6, 5e6, 10e6, 20e6, 40e6, 56e6],
+        gains: &[0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 76.0],
+        gain_step: Some(1.0),
+        freq_range: (70e6, 6e9),
+        sample_rate_range: (200e3, 56e6),
+    },
+    HardwareProfile {
+        name: "RTL-SDR",
+        antenna_ports: &["RX"],
+        sample_rates: &[
+            250e3, 1.024e6, 1.4e6, 1.8e6, 1.92e6, 2.048e6, 2.4e6, 2.56e6, 2.88e6, 3.2e6,
+        ],
+        // The R820T tuner only takes these gains
+        gains: &[
+            0.0, 0.9, 1.4, 2.7, 3.7, 7.7, 8.7, 12.5, 14.4, 15.7, 16.6, 19.7, 20.7, 22.9, 25.4,
+            28.0, 29.7, 32.8, 33.8, 36.4, 37.2, 38.6, 40.2, 42.1, 43.4, 43.9, 44.5, 48.0, 49.6,
+        ],
+        gain_step: None,
+        // R820T tuner; rates above 2.4 MHz may drop samples
+        freq_range: (24e6, 1766e6),
+        sample_rate_range: (225e3, 3.2e6),
+    },
+    HardwareProfile {
+        name: "HackRF One",
+        antenna_ports: &["RX"],
+        sample_rates: &[2e6, 4e6, 8e6, 10e6, 16e6, 20e6],
+        gains: &[0.0, 8.0, 16.0, 24.0, 32.0, 40.0],
+        // VGA steps; the LNA moves in 8 dB steps
+        gain_step: Some(2.0),
+        freq_range: (1e6, 6e9),
+        sample_rate_range: (2e6, 20e6),
+    },
+];
+
+/// The profile called `name`, if it is one we know
+pub fn find(name: &str) -> Option<&'static HardwareProfile> {
+    PROFILES.iter().find(|x| x.name == name)
+}
+
+/// dB the gain steppers move by without a profile
+const DEFAULT_GAIN_STEP: f64 = 1.0;
+
+/// How far a gain stepper moves in `direction` (1 or -1)
+pub fn gain_delta(profile: Option<&HardwareProfile>, direction: f64) -> f64 {
+    direction
+        * profile
+            .map_or(Some(DEFAULT_GAIN_STEP), |x| x.gain_step)
+            .unwrap_or(1.0)
+}
+
+/// `gain` moved by `delta`. Within a profile's range the result is
+/// clamped to it, and tuners that only take listed gains go to the next
+/// listed one in `delta`'s direction. Without a profile it stays at or
+/// above 0.
+pub fn step_gain(gain: f64, delta: f64, profile: Option<&HardwareProfile>) -> f64 {
+    let Some(profile) = profile.filter(|x| !x.gains.is_empty()) else {
+        return (gain + delta).max(0.0);
+    };
+    let min = profile.gains.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = profile
+        .gains
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    if profile.gain_step.is_some() {
+        return (gain + delta).clamp(min, max);
+    }
+    let next = if delta > 0.0 {
+        profile
+            .gains
+            .iter()
+            .copied()
+            .filter(|x| *x > gain)
+            .fold(f64::INFINITY, f64::min)
+    } else {
+        profile
+            .gains
+            .iter()
+            .copied()
+            .filter(|x| *x < gain)
+            .fold(f64::NEG_INFINITY, f64::max)
+    };
+    if next.is_finite() {
+        next
+    } else {
+        gain.clamp(min, max)
+    }
+}
+
+/// Ports to offer for `profile`, falling back to the common port names
+pub fn antenna_ports(profile: Option<&HardwareProfile>) -> &'static [&'static str] {
+    profile.map_or(DEFAULT_ANTENNA_PORTS, |x| x.antenna_ports)
+}
+
+#[derive(Debug, Clone)]
+pub enum DeviceMsg {
+    Select(Option<HardwareProfile>),
+    // Copies the profile's presets into capture-setting lists that are empty
+    FillDefaults,
+}
+
+impl From<DeviceMsg> for Message {
+    fn from(value: DeviceMsg) -> Self {
+        Message::Device(value)
+    }
+}
+
+pub fn view<'a>(profile: Option<&'static HardwareProfile>) -> iced::Element<'a, Message> {
+    iced::widget::row![
+        iced::widget::text("Hardware profile"),
+        iced::widget::pick_list(PROFILES, profile.copied(), |c| DeviceMsg::Select(Some(c))
+            .into())
+        .placeholder("none"),
+        iced::widget::button("Clear").on_press(DeviceMsg::Select(None).into()),
+    ]
+    .push_maybe(profile.map(|_| {
+        iced::widget::button("Fill Empty Capture Settings").on_press(DeviceMsg::FillDefaults.into())
+    }))
+    .push_maybe(profile.map(|x| {
+        iced::widget::text(format!(
+            "tunes {}, samples at {}",
+            x.freq_limits(),
+            x.sample_rate_limits()
+        ))
+        .size(12)
+    }))
+    .spacing(10)
+    .into()
+}
@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use crate::config::Config;
+
+/// Formats a config file can be written in, chosen by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// A serializer or parser failure, from whichever backend ran
+#[derive(Debug)]
+pub enum FormatError {
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    TomlWrite(toml::ser::Error),
+    TomlRead(toml::de::Error),
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "{e}"),
+            Self::Yaml(e) => write!(f, "{e}"),
+            Self::TomlWrite(e) => write!(f, "{e}"),
+            Self::TomlRead(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl FileFormat {
+    /// Every format, JSON first as the default
+    pub const ALL: [FileFormat; 3] = [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml];
+
+    /// Format of the file at `path`. Unknown or missing extensions are
+    /// JSON, which is what the capture scripts read.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|x| x.to_string_lossy().to_ascii_lowercase());
+        Self::ALL
+            .into_iter()
+            .find(|x| {
+                x.extensions()
+                    .iter()
+                    .any(|e| Some(*e) == extension.as_deref())
+            })
+            .unwrap_or(FileFormat::Json)
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["json"],
+            Self::Yaml => &["yaml", "yml"],
+            Self::Toml => &["toml"],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+        }
+    }
+
+    /// `pretty` only affects JSON; YAML and TOML are always laid out
+    pub fn write(self, conf: &Config, pretty: bool) -> Result<String, FormatError> {
+        match self {
+            Self::Json if pretty => serde_json::to_string_pretty(conf).map_err(FormatError::Json),
+            Self::Json => serde_json::to_string(conf).map_err(FormatError::Json),
+            Self::Yaml => serde_yaml::to_string(conf).map_err(FormatError::Yaml),
+            Self::Toml => toml::to_string_pretty(conf).map_err(FormatError::TomlWrite),
+        }
+    }
+
+    /// The file's contents as a JSON value, whatever it was written in, so
+    /// every format goes through the same checks on open
+    pub fn read(self, bytes: &[u8]) -> Result<serde_json::Value, FormatError> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(FormatError::Json),
+            Self::Yaml => serde_yaml::from_slice(bytes)
+                .map(yaml_to_json)
+                .map_err(FormatError::Yaml),
+            Self::Toml => {
+                let text = String::from_utf8_lossy(bytes);
+                toml::from_str(&text).map_err(FormatError::TomlRead)
+            }
+        }
+    }
+}
+
+/// YAML as the JSON serde would have written. serde_yaml writes an enum
+/// variant holding data as a tag (`!weekly`), which is turned back into
+/// the `{"weekly": ...}` map JSON uses; other keys become strings.
+fn yaml_to_json(value: serde_yaml::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(x) => Value::Bool(x),
+        serde_yaml::Value::Number(x) => match (x.as_u64(), x.as_i64(), x.as_f64()) {
+            (Some(x), _, _) => Value::from(x),
+            (None, Some(x), _) => Value::from(x),
+            (None, None, x) => x
+                .and_then(serde_json::Number::from_f64)
+                .map_or(Value::Null, Value::Number),
+        },
+        serde_yaml::Value::String(x) => Value::String(x),
+        serde_yaml::Value::Sequence(x) => Value::Array(x.into_iter().map(yaml_to_json).collect()),
+        serde_yaml::Value::Mapping(x) => Value::Object(
+            x.into_iter()
+                .map(|(key, value)| {
+                    let key = match yaml_to_json(key) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, yaml_to_json(value))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(x) => {
+            let tag = x.tag.to_string().trim_start_matches('!').to_string();
+            Value::Object([(tag, yaml_to_json(x.value))].into_iter().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config using what each format has to represent in its own way: a
+    /// weekly slot (an enum holding data), a range (a nested table) and
+    /// fields from a newer version at several levels
+    const FIXTURE: &str = r#"{
+        "version": 1,
+        "frequencies": {
+            "ism": [
+                {"name": "ism_433", "freq": 433920000.0, "description": "remotes", "tags": ["ook"], "colour": "red"},
+                {"name": "fm", "freq": 88000000.0, "description": "", "range": {"stop": 108000000.0, "step": 200000.0}}
+            ]
+        },
+        "capture_settings": {
+            "sample_rates": [1000000.0, 2400000.0], "durations": [60.0], "gains": [20.0, 40.5],
+            "compression": "zstd", "compression_level": 3, "datatype": "ci16"
+        },
+        "collection_modes": {
+            "survey": {"sample_rate": 1000000.0, "duration": 60.0, "gain": 20.0, "bandwidth": 5e5}
+        },
+        "scheduling": {
+            "time_slots": [
+                {"name": "weekdays", "start": "08:00", "end": "17:30", "recurrence": {"weekly": {"days": ["Mon", "Tue", "Fri"]}}},
+                {"name": "night", "start": "23:00", "end": "01:00", "crosses_midnight": true, "enabled": false, "priority": 2}
+            ]
+        },
+        "notes": "",
+        "operator": {"name": "field team", "site": 4}
+    }"#;
+
+    fn fixture() -> Config {
+        crate::migrate::read(serde_json::from_str(FIXTURE).unwrap())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn round_trips_in_every_format() {
+        let config = fixture();
+        let expected = serde_json::to_value(&config).unwrap();
+        assert_eq!(expected["operator"]["site"], 4);
+        assert_eq!(expected["scheduling"]["time_slots"][1]["priority"], 2);
+        assert_eq!(expected["collection_modes"]["survey"]["bandwidth"], 5e5);
+        for format in FileFormat::ALL {
+            let written = format.write(&config, true).unwrap();
+            let (back, _) = crate::migrate::read(format.read(written.as_bytes()).unwrap()).unwrap();
+            assert_eq!(
+                serde_json::to_value(&back).unwrap(),
+                expected,
+                "{}",
+                format.name()
+            );
+            assert_eq!(
+                format.write(&back, true).unwrap(),
+                written,
+                "{}",
+                format.name()
+            );
+        }
+    }
+
+    #[test]
+    fn nan_is_refused_in_every_format() {
+        use crate::collection_modes::{CollectionModesMsg, CollectionMsg};
+
+        let mut config = fixture();
+        let change =
+            CollectionModesMsg::Change(("survey".to_string(), CollectionMsg::ChangeGain(f64::NAN)));
+        let _ = config.update(crate::Message::CollectionModes(change));
+        assert!(
+            FileFormat::Json
+                .write(&config, false)
+                .unwrap()
+                .contains(r#""gain":null"#)
+        );
+        for format in FileFormat::ALL {
+            let read = format
+                .write(&config, true)
+                .ok()
+                .and_then(|x| format.read(x.as_bytes()).ok());
+            assert!(
+                read.is_none_or(|x| crate::migrate::read(x).is_err()),
+                "{} kept NaN",
+                format.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use iced::widget::text_input;
+
+/// Text fields that take part in Tab navigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Field {
+    GroupName,
+    FreqName,
+    FreqValue,
+    FreqDescription,
+    ModeName,
+    // Modes now pick these from lists; kept so saved tab orders still load
+    ModeSampleRate,
+    ModeDuration,
+    ModeGain,
+    SlotName,
+    SlotStart,
+    SlotEnd,
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GroupName => write!(f, "group name"),
+            Self::FreqName => write!(f, "frequency name"),
+            Self::FreqValue => write!(f, "frequency value"),
+            Self::FreqDescription => write!(f, "frequency description"),
+            Self::ModeName => write!(f, "mode name"),
+            Self::ModeSampleRate => write!(f, "mode sample rate"),
+            Self::ModeDuration => write!(f, "mode duration"),
+            Self::ModeGain => write!(f, "mode gain"),
+            Self::SlotName => write!(f, "slot name"),
+            Self::SlotStart => write!(f, "slot start"),
+            Self::SlotEnd => write!(f, "slot end"),
+        }
+    }
+}
+
+/// Visit every field, top to bottom as laid out in each section
+pub const DEFAULT_TAB_ORDER: &[Field] = &[
+    Field::GroupName,
+    Field::FreqName,
+    Field::FreqValue,
+    Field::FreqDescription,
+    Field::ModeName,
+    Field::SlotName,
+    Field::SlotStart,
+    Field::SlotEnd,
+];
+
+/// Stable id of a field. `key` identifies the item the field belongs to
+/// (group or mode name, frequency or slot id) so the id survives
+/// re-renders and can be targeted by focus operations.
+pub fn id(field: Field, key: impl std::fmt::Display) -> text_input::Id {
+    text_input::Id::new(format!("{field:?}:{key}"))
+}
+
+/// Focuses a field and selects its contents, so typing replaces them
+pub fn focus_and_select<T: Send + 'static>(id: text_input::Id) -> iced::Task<T> {
+    text_input::focus(id.clone()).chain(text_input::select_all(id))
+}
+
+/// Ids of one row of fields, in the position `order` gives them. Fields
+/// missing from `order` are skipped by Tab.
+pub fn row(order: &[Field], fields: &[Field], key: impl std::fmt::Display) -> Vec<text_input::Id> {
+    order
+        .iter()
+        .filter(|x| fields.contains(x))
+        .map(|x| id(*x, &key))
+        .collect()
+}
+
+/// The id after (or before) `current` in `chain`, wrapping around at the
+/// ends. Starts from the first field when nothing is focused.
+pub fn step(
+    chain: &[text_input::Id],
+    current: Option<iced::advanced::widget::Id>,
+    forward: bool,
+) -> Option<text_input::Id> {
+    let position = current.and_then(|current| {
+        chain
+            .iter()
+            .position(|x| iced::advanced::widget::Id::from(x.clone()) == current)
+    });
+    let next = match (position, forward) {
+        (None, _) => 0,
+        (Some(i), true) => (i + 1) % chain.len().max(1),
+        (Some(i), false) => (i + chain.len() - 1) % chain.len().max(1),
+    };
+    chain.get(next).cloned()
+}
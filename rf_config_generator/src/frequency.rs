@@ -1,66 +1,408 @@
-use std::collections::{HashMap, hash_map};
+use std::collections::{BTreeMap, HashMap, HashSet, hash_map};
 
-use iced::{Pixels, widget::column};
-use serde::ser::SerializeStruct;
+use iced::widget::column;
 
-use crate::Message;
+use crate::{
+    Message,
+    device::HardwareProfile,
+    focus::{self, Field},
+    templates,
+    toolbar::ToolbarMsg,
+    utils,
+    validation::{self, ValidationIssue},
+};
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
-struct Frequency {
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Frequency {
     pub name: String,
     pub freq: f64,
-    #[serde(skip, default="rand::random")]
+    #[serde(skip, default = "rand::random")]
     id: u64,
     pub description: String,
+    // Disabled frequencies stay in the config but are left out of
+    // task expansion and selection exports
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Set when this entry stands for a whole sweep starting at `freq`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+    // Free-form labels for filtering across groups
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Tag being typed, added on submit
+    #[serde(skip)]
+    tag_draft: String,
+    // Frequency text as typed, kept so the field shows it even when it
+    // doesn't parse; None shows `freq`
+    #[serde(skip)]
+    freq_input: Option<String>,
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+/// Most frequencies one range entry is expanded to. Longer ranges are
+/// cut short and reported by validation, so a mistyped step can't make
+/// the editor allocate billions of points.
+pub const MAX_RANGE_POINTS: usize = 10_000;
+
+/// Evenly spaced frequencies from the owning entry's `freq` up to `stop`
+#[derive(
+    Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Range {
+    pub stop: f64,
+    pub step: f64,
+}
+
+/// Evenly spaced single frequencies in a group that could be stored as one range
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    ids: Vec<u64>,
+    start: f64,
+    stop: f64,
+    step: f64,
+}
+
+/// Capture priorities a group can have. Higher ones are captured first;
+/// 0, the default, keeps the groups in display order.
+pub const PRIORITIES: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+/// Shortest run worth collapsing into a range
+const MIN_RUN: usize = 3;
+
+/// Finds runs of at least [`MIN_RUN`] single frequencies with equal spacing,
+/// ignoring their order in the group. Members of a run share an enabled state
+/// so collapsing them doesn't change what gets captured.
+pub fn find_runs(freqs: &[Frequency]) -> Vec<Run> {
+    let mut singles: Vec<&Frequency> = freqs.iter().filter(|x| x.range.is_none()).collect();
+    singles.sort_by(|a, b| a.freq.total_cmp(&b.freq));
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i + 1 < singles.len() {
+        let step = singles[i + 1].freq - singles[i].freq;
+        let mut end = i + 1;
+        if step > 0.0 {
+            while end + 1 < singles.len()
+                && ((singles[end + 1].freq - singles[end].freq) - step).abs() <= step * 1e-9
+                && singles[end + 1].enabled == singles[i].enabled
+            {
+                end += 1;
+            }
+        }
+        if end + 1 - i >= MIN_RUN && singles[i + 1].enabled == singles[i].enabled {
+            runs.push(Run {
+                ids: singles[i..=end].iter().map(|x| x.id).collect(),
+                start: singles[i].freq,
+                stop: singles[end].freq,
+                step,
+            });
+            i = end + 1;
+        } else {
+            i += 1;
+        }
+    }
+    runs
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} Hz to {} Hz in {} Hz steps ({} frequencies)",
+            self.start,
+            self.stop,
+            self.step,
+            self.ids.len()
+        )
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(
+    Debug, Default, PartialEq, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 #[serde(transparent)]
-struct FreqGroup {
+pub struct FreqGroup {
     pub vec: Vec<Frequency>,
-    #[serde(skip, default="default_instant")]
-    time: std::time::Instant,
+    // Position among the groups. Groups are plain arrays in the file, so
+    // this is saved as the order they are written in.
+    #[serde(skip)]
+    order: u64,
 }
 
-impl From<Vec<Frequency>> for FreqGroup {
-    fn from(value: Vec<Frequency>) -> Self {
-        Self {
-            vec: value,
-            time: std::time::Instant::now(),
+/// Frequencies closer than this (Hz) are taken to be the same one
+const SAME_FREQUENCY: f64 = 1e-3;
+
+impl FreqGroup {
+    /// Ids of the frequencies whose value another frequency in the group
+    /// shares, range points included. Zeros are what Add Frequency
+    /// inserts, so they aren't counted until edited.
+    pub fn duplicate_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.duplicates().into_iter().map(|(_, id)| id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Every captured value another frequency of the group also captures,
+    /// with the id of each frequency capturing it, in ascending order
+    fn duplicates(&self) -> Vec<(f64, u64)> {
+        let mut values: Vec<(f64, u64)> = self
+            .vec
+            .iter()
+            .filter(|x| x.freq != 0.0)
+            .flat_map(|x| x.points().into_iter().map(|point| (point, x.id)))
+            .collect();
+        values.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut duplicates = Vec::new();
+        for (i, (freq, id)) in values.iter().enumerate() {
+            // A range's own points are a step apart, so never its duplicates
+            let near = |other: Option<&(f64, u64)>| {
+                other.is_some_and(|x| x.1 != *id && (x.0 - freq).abs() < SAME_FREQUENCY)
+            };
+            if near(i.checked_sub(1).and_then(|x| values.get(x))) || near(values.get(i + 1)) {
+                duplicates.push((*freq, *id));
+            }
         }
+        duplicates
     }
 }
 
-fn default_instant() -> std::time::Instant {
-    std::time::Instant::now()
-}
+impl utils::Ordered for FreqGroup {
+    fn order(&self) -> u64 {
+        self.order
+    }
 
-impl PartialOrd for FreqGroup {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.time.partial_cmp(&other.time)?)
+    fn set_order(&mut self, order: u64) {
+        self.order = order;
     }
 }
 
-impl Default for FreqGroup {
-    fn default() -> Self {
+impl From<Vec<Frequency>> for FreqGroup {
+    fn from(value: Vec<Frequency>) -> Self {
         Self {
-            vec: Vec::default(),
-            time: std::time::Instant::now(),
+            vec: value,
+            order: 0,
         }
     }
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(transparent)]
 pub struct FrequencyConfig {
+    #[serde(
+        serialize_with = "utils::serialize_in_order",
+        deserialize_with = "utils::deserialize_numbered"
+    )]
     frequencies: HashMap<String, FreqGroup>,
+    #[serde(skip)]
+    active_group: Option<String>,
+    // Groups whose frequencies are hidden in the view
+    #[serde(skip)]
+    collapsed: HashSet<String>,
+    // Result of the last range search per group, awaiting confirmation
+    #[serde(skip)]
+    found_runs: HashMap<String, Vec<Run>>,
+    #[serde(skip)]
+    tag_filter: Option<String>,
+    #[serde(skip)]
+    renames: HashMap<String, Rename>,
+    // Scroll offset of each virtualized group
+    #[serde(skip)]
+    scroll: HashMap<String, f32>,
+    // Key of the group owning each frequency id, so edits go straight to
+    // the right group. Kept up to date by every path that adds, moves or
+    // removes frequencies; it starts empty after loading and is rebuilt
+    // whenever a lookup misses.
+    #[serde(skip)]
+    index: HashMap<u64, String>,
+}
+
+/// Compares the groups, not view state like collapsed groups or scrolling
+impl PartialEq for FrequencyConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.frequencies == other.frequencies
+    }
 }
 
 #[derive(Debug, Clone)]
-enum FreqAction {
+#[allow(clippy::enum_variant_names)]
+pub enum FreqAction {
     NameUpdated((u64, String)),
+    // Text typed into the frequency field, units allowed
     FreqUpdated((u64, String)),
+    // Enter in the frequency field: show the value as plain Hz
+    FreqSubmitted(u64),
     DescUpdated((u64, String)),
+    EnabledUpdated((u64, bool)),
+    TagDraftUpdated((u64, String)),
+    TagAdded(u64),
+    TagRemoved((u64, String)),
+}
+
+/// Groups with more rows than this only build widgets for the rows in view
+const VIRTUALIZE_ABOVE: usize = 50;
+/// Height every row is clipped to in a virtualized group, so positions can
+/// be computed without laying rows out
+const ROW_HEIGHT: f32 = 200.0;
+/// Height of a virtualized group's scrolling window
+const WINDOW_HEIGHT: f32 = 800.0;
+/// Rows built beyond each edge of the window, so fast scrolling doesn't
+/// show blank space
+const OVERSCAN: usize = 2;
+
+/// Rows of a list of `len` equal-height rows that intersect a window of
+/// `height` scrolled down by `offset`, with [`OVERSCAN`] rows either side.
+/// At most `height / row_height + 2 * OVERSCAN + 1` rows, however long the
+/// list.
+pub fn visible_rows(
+    offset: f32,
+    height: f32,
+    row_height: f32,
+    len: usize,
+) -> std::ops::Range<usize> {
+    let first = (offset.max(0.0) / row_height).floor() as usize;
+    let last = ((offset.max(0.0) + height) / row_height).ceil() as usize;
+    first.saturating_sub(OVERSCAN).min(len)..(last + OVERSCAN).min(len)
+}
+
+/// A rename being set up for a group
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    template: String,
+    // Leave frequencies that already have a name alone
+    only_unnamed: bool,
+}
+
+impl Default for Rename {
+    fn default() -> Self {
+        Self {
+            template: "scan_{index:3}".to_string(),
+            only_unnamed: false,
+        }
+    }
+}
+
+/// Renders a rename template for the `index`th (from 1) renamed frequency.
+/// Tokens: `{index}` (or `{index:N}`, zero padded to N digits), `{mhz}`,
+/// `{hz}` and `{name}` for the current name. Anything else is copied as is.
+pub fn render_name(template: &str, index: usize, freq: &Frequency) -> String {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}').map(|x| open + x) else {
+            break;
+        };
+        let token = &rest[open + 1..close];
+        let replacement = match token {
+            "index" => Some(index.to_string()),
+            "mhz" => Some((freq.freq / 1e6).to_string()),
+            "hz" => Some(freq.freq.to_string()),
+            "name" => Some(freq.name.clone()),
+            _ => token
+                .strip_prefix("index:")
+                .and_then(|width| width.parse::<usize>().ok())
+                .map(|width| format!("{index:0width$}")),
+        };
+        name.push_str(replacement.as_deref().unwrap_or(&rest[open..=close]));
+        rest = &rest[close + 1..];
+    }
+    name.push_str(rest);
+    name
+}
+
+/// Hz with thousands separators, e.g. `101,100,000 Hz`
+pub fn format_hz(freq: f64) -> String {
+    let text = format!("{}", freq.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if freq < 0.0 { "-" } else { "" };
+    match fraction {
+        "" => format!("{sign}{grouped} Hz"),
+        _ => format!("{sign}{grouped}.{fraction} Hz"),
+    }
+}
+
+/// Fields of one CSV line. A field in double quotes may hold commas, and
+/// `""` inside it is a literal quote.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Frequencies in CSV rows of `name,freq,description`, and how many rows
+/// were skipped for not fitting. The description may be left out and
+/// further columns are ignored. `freq` takes units like a typed value. A
+/// first row whose `freq` isn't a number is taken as a header and isn't
+/// counted as skipped.
+pub fn parse_csv(text: &str) -> (Vec<Frequency>, usize) {
+    let (mut freqs, mut skipped) = (Vec::new(), 0);
+    // Spreadsheets often start their CSV with a byte order mark
+    let lines = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty());
+    for (i, line) in lines.enumerate() {
+        let fields = csv_fields(line);
+        match fields.get(1).and_then(|x| parse_frequency(x)) {
+            Some(value) => {
+                let mut freq = Frequency::new(rand::random());
+                freq.name = fields[0].trim().to_string();
+                freq.freq = value;
+                freq.description = fields
+                    .get(2)
+                    .map(|x| x.trim().to_string())
+                    .unwrap_or_default();
+                freqs.push(freq);
+            }
+            None if i == 0 => (),
+            None => skipped += 1,
+        }
+    }
+    (freqs, skipped)
+}
+
+/// Parses a frequency in Hz. Accepts an optional unit suffix, either
+/// spelled out (`Hz`, `kHz`, `MHz`, `GHz`) or shortened (`k`, `M`, `G`),
+/// ignoring case and whitespace. A bare number is taken as Hz.
+pub fn parse_frequency(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let lower = s.to_ascii_lowercase();
+    let s = lower.strip_suffix("hz").unwrap_or(&lower).trim_end();
+    let (number, multiplier) = match s.char_indices().last()? {
+        (i, 'k') => (&s[..i], 1e3),
+        (i, 'm') => (&s[..i], 1e6),
+        (i, 'g') => (&s[..i], 1e9),
+        _ => (s, 1.0),
+    };
+    let parsed = utils::parse_finite(number)?;
+    Some(parsed * multiplier).filter(|x| x.is_finite())
 }
 
 impl FreqAction {
@@ -68,7 +410,12 @@ impl FreqAction {
         match self {
             Self::NameUpdated((x, _)) => *x,
             Self::FreqUpdated((x, _)) => *x,
+            Self::FreqSubmitted(x) => *x,
             Self::DescUpdated((x, _)) => *x,
+            Self::EnabledUpdated((x, _)) => *x,
+            Self::TagDraftUpdated((x, _)) => *x,
+            Self::TagAdded(x) => *x,
+            Self::TagRemoved((x, _)) => *x,
         }
     }
 }
@@ -78,9 +425,39 @@ pub enum FrequencyMessage {
     AddGroup,
     GroupNameUpdated((String, String)),
     AddFreq(String),
+    // Reads the clipboard and appends one frequency per value in it
+    PasteList(String),
+    PastedList((String, Option<String>)),
+    // Contents of a CSV file to append to a group, one frequency per row
+    ImportedCsv((String, String)),
     DelGroup(String),
     DelFreq(u64),
     FreqAction(FreqAction),
+    ToggleGroup(String),
+    ExpandAll,
+    CollapseAll,
+    DelEmptyGroups,
+    // Looks for evenly spaced frequencies in a group and shows what was found
+    FindRanges(String),
+    CollapseRanges(String),
+    DismissRanges(String),
+    ExpandRange(u64),
+    // Shows only frequencies carrying the tag
+    FilterTag(Option<String>),
+    // Renaming every frequency of a group from a template, previewed first
+    StartRename(String),
+    RenameTemplate((String, String)),
+    RenameOnlyUnnamed((String, bool)),
+    ApplyRename(String),
+    CancelRename(String),
+    Scrolled((String, f32)),
+    // Adds a ready-made group, e.g. from a template. The name gets a
+    // number appended if it is taken.
+    InsertGroup((String, Vec<Frequency>)),
+    MoveGroupUp(String),
+    MoveGroupDown(String),
+    // Capture priority of a group, kept by the config next to the groups
+    Priority((String, u8)),
 }
 
 impl From<FreqAction> for FrequencyMessage {
@@ -108,35 +485,183 @@ impl Frequency {
             freq: 0.0,
             id,
             description: String::new(),
+            enabled: true,
+            range: None,
+            tags: Vec::new(),
+            tag_draft: String::new(),
+            freq_input: None,
+            unknown: Default::default(),
         }
     }
-    pub fn update(&mut self, msg: FreqAction) {
 
-        if self.id != msg.get_id() { return; }
+    /// How many frequencies the range covers, uncapped; None without a
+    /// valid range
+    fn range_len(&self) -> Option<usize> {
+        let range = self.range.filter(|x| x.step > 0.0 && x.stop >= self.freq)?;
+        // `as` saturates, so absurd ranges can't overflow
+        Some((((range.stop - self.freq) / range.step + 1e-9).floor() as usize).saturating_add(1))
+    }
+
+    /// Length of [`Frequency::points`], worked out without building it
+    pub fn point_count(&self) -> usize {
+        self.range_len().map_or(1, |x| x.min(MAX_RANGE_POINTS))
+    }
+
+    /// The uncapped length of a range covering more than
+    /// [`MAX_RANGE_POINTS`] frequencies
+    pub fn range_too_long(&self) -> Option<usize> {
+        self.range_len().filter(|x| *x > MAX_RANGE_POINTS)
+    }
+
+    /// Lowest and highest frequency this entry captures
+    pub fn span(&self) -> (f64, f64) {
+        let step = self.range.map_or(0.0, |x| x.step);
+        (
+            self.freq,
+            self.freq + (self.point_count() - 1) as f64 * step,
+        )
+    }
+
+    /// Every frequency this entry captures: its own, or each step of its
+    /// range, at most [`MAX_RANGE_POINTS`] of them
+    pub fn points(&self) -> Vec<f64> {
+        let step = self.range.map_or(0.0, |x| x.step);
+        (0..self.point_count())
+            .map(|i| self.freq + i as f64 * step)
+            .collect()
+    }
+
+    pub fn update(&mut self, msg: FreqAction) {
+        if self.id != msg.get_id() {
+            return;
+        }
         match msg {
             FreqAction::NameUpdated((_, name)) => self.name = name,
             FreqAction::DescUpdated((_, desc)) => self.description = desc,
+            FreqAction::EnabledUpdated((_, enabled)) => self.enabled = enabled,
+            FreqAction::TagDraftUpdated((_, draft)) => self.tag_draft = draft,
+            FreqAction::TagAdded(_) => {
+                let tag = std::mem::take(&mut self.tag_draft).trim().to_string();
+                if !tag.is_empty() && !self.tags.contains(&tag) {
+                    self.tags.push(tag);
+                }
+            }
+            FreqAction::TagRemoved((_, tag)) => self.tags.retain(|x| *x != tag),
             FreqAction::FreqUpdated((_, freq)) => {
-                let Ok(parsed): Result<f64, _> = freq.parse() else {
-                    return;
-                };
-                self.freq = parsed;
+                let parsed = parse_frequency(&freq);
+                self.freq_input = Some(freq);
+                if let Some(parsed) = parsed {
+                    self.freq = parsed;
+                }
+            }
+            FreqAction::FreqSubmitted(_) => {
+                if self
+                    .freq_input
+                    .as_deref()
+                    .and_then(parse_frequency)
+                    .is_some()
+                {
+                    self.freq_input = None;
+                }
             }
         }
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
+    /// `duplicate` marks a frequency another in its group shares, and
+    /// `profile` is the hardware whose tuning range it is checked against
+    pub fn view(
+        &self,
+        duplicate: bool,
+        profile: Option<&HardwareProfile>,
+    ) -> iced::Element<'_, Message> {
+        let invalid = self
+            .freq_input
+            .as_deref()
+            .is_some_and(|x| parse_frequency(x).is_none());
+        let (low, high) = self.span();
+        let untunable = profile.filter(|x| !x.tunes_to(low) || !x.tunes_to(high));
         let column = iced::widget::column![
             iced::widget::text_input("freq_name", &self.name)
+                .id(focus::id(Field::FreqName, self.id))
                 .on_input(|c| Message::from(FreqAction::NameUpdated((self.id, c)))),
-            iced::widget::text_input("frequency_value", &format!("{}", self.freq))
-                .on_input(|c| Message::from(FreqAction::FreqUpdated((self.id, c)))),
+            iced::widget::text_input(
+                "frequency_value",
+                self.freq_input
+                    .as_deref()
+                    .unwrap_or(&format!("{}", self.freq)),
+            )
+            .id(focus::id(Field::FreqValue, self.id))
+            .on_input(|c| Message::from(FreqAction::FreqUpdated((self.id, c))))
+            .on_submit(Message::from(FreqAction::FreqSubmitted(self.id)))
+            .style(if untunable.is_some() {
+                validation::invalid_input
+            } else if duplicate {
+                validation::warning_input
+            } else {
+                iced::widget::text_input::default
+            }),
+            // Readout of how the typed value was understood
+            if invalid {
+                iced::widget::text("invalid")
+                    .size(12)
+                    .style(iced::widget::text::danger)
+            } else if let Some(profile) = untunable {
+                iced::widget::text(format!(
+                    "= {}, outside the {} range of {}",
+                    format_hz(self.freq),
+                    profile.name,
+                    profile.freq_limits()
+                ))
+                .size(12)
+                .style(iced::widget::text::danger)
+            } else if duplicate {
+                iced::widget::text(format!("= {} (duplicate)", format_hz(self.freq)))
+                    .size(12)
+                    .style(validation::warning)
+            } else {
+                iced::widget::text(format!("= {}", format_hz(self.freq))).size(12)
+            },
             iced::widget::text_input("description (human readable)", &self.description)
+                .id(focus::id(Field::FreqDescription, self.id))
                 .on_input(|c| Message::from(FreqAction::DescUpdated((self.id, c)))),
         ];
+        let tags = iced::widget::row(self.tags.iter().map(|tag| {
+            iced::widget::button(iced::widget::text(format!("{tag} x")).size(12))
+                .style(iced::widget::button::secondary)
+                .padding([2, 6])
+                .on_press(Message::from(FreqAction::TagRemoved((
+                    self.id,
+                    tag.clone(),
+                ))))
+                .into()
+        }))
+        .push(
+            iced::widget::text_input("add tag", &self.tag_draft)
+                .on_input(|c| Message::from(FreqAction::TagDraftUpdated((self.id, c))))
+                .on_submit(Message::from(FreqAction::TagAdded(self.id)))
+                .width(120),
+        )
+        .spacing(4);
+        let column = column.push(tags).push_maybe(self.range.map(|range| {
+            iced::widget::row![
+                iced::widget::text(format!(
+                    "to {} Hz in {} Hz steps ({} frequencies)",
+                    range.stop,
+                    range.step,
+                    self.point_count()
+                )),
+                iced::widget::button("Expand Range")
+                    .on_press(Message::from(FrequencyMessage::ExpandRange(self.id))),
+            ]
+            .spacing(8)
+        }));
         iced::widget::row![
-            iced::widget::button("Delete")
-                .on_press(Message::from(FrequencyMessage::DelFreq(self.id))),
+            iced::widget::column![
+                iced::widget::button("Delete")
+                    .on_press(Message::from(FrequencyMessage::DelFreq(self.id))),
+                iced::widget::checkbox("enabled", self.enabled)
+                    .on_toggle(|c| Message::from(FreqAction::EnabledUpdated((self.id, c)))),
+            ],
             column,
         ]
         .into()
@@ -144,84 +669,897 @@ impl Frequency {
 }
 
 impl FrequencyConfig {
-    pub fn update(&mut self, msg: Message) -> Message {
-        if let Message::Frequency(f) = msg {
-            match f {
-                FrequencyMessage::AddGroup => {
-                    let mut rand_byte: u8 = rand::random();
-                    let mut rand_name = format!("group_{:x}", rand_byte);
-                    while self.frequencies.contains_key(&rand_name) {
-                        rand_byte = rand::random();
-                        rand_name = format!("group_{:x}", rand_byte);
+    /// Groups in display order
+    pub fn sorted(&self) -> Vec<(&String, &FreqGroup)> {
+        utils::sorted(&self.frequencies)
+    }
+
+    fn move_by(&mut self, key: &str, up: bool) {
+        let keys: Vec<String> = self.sorted().into_iter().map(|(k, _)| k.clone()).collect();
+        let Some(from) = keys.iter().position(|x| x == key) else {
+            return;
+        };
+        let to = if up {
+            from.checked_sub(1)
+        } else {
+            Some(from + 1)
+        };
+        if let Some(to) = to {
+            utils::reorder(&mut self.frequencies, keys, from, to);
+        }
+    }
+
+    pub fn group(&self, key: &str) -> Option<&FreqGroup> {
+        self.frequencies.get(key)
+    }
+
+    /// Ids of the section's fields in Tab order
+    pub fn focus_chain(&self, order: &[Field]) -> Vec<iced::widget::text_input::Id> {
+        let mut chain = Vec::new();
+        for (key, group) in self.sorted() {
+            chain.extend(focus::row(order, &[Field::GroupName], key));
+            if self.collapsed.contains(key) {
+                continue;
+            }
+            for freq in &group.vec {
+                chain.extend(focus::row(
+                    order,
+                    &[Field::FreqName, Field::FreqValue, Field::FreqDescription],
+                    freq.id,
+                ));
+            }
+        }
+        chain
+    }
+
+    /// Keeps only the frequencies matching `keep`, dropping groups left empty
+    pub fn retain(&mut self, keep: impl Fn(&Frequency) -> bool) {
+        self.frequencies
+            .values_mut()
+            .for_each(|group| group.vec.retain(&keep));
+        self.frequencies.retain(|_, group| !group.vec.is_empty());
+        self.collapsed
+            .retain(|key| self.frequencies.contains_key(key));
+        self.active_group = None;
+        self.reindex();
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .frequencies
+            .iter()
+            .flat_map(|(key, group)| group.vec.iter().map(move |x| (x.id, key.clone())))
+            .collect();
+    }
+
+    /// Key of the group holding frequency `id` and its position there
+    fn locate(&mut self, id: u64) -> Option<(String, usize)> {
+        let find = |this: &Self| {
+            let key = this.index.get(&id)?;
+            let position = this
+                .frequencies
+                .get(key)?
+                .vec
+                .iter()
+                .position(|x| x.id == id)?;
+            Some((key.clone(), position))
+        };
+        find(self).or_else(|| {
+            self.reindex();
+            find(self)
+        })
+    }
+
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for (key, group) in self.sorted() {
+            issues.extend(validation::file_name_issue("Frequencies", "group", key));
+            if group.vec.is_empty() {
+                issues.push(ValidationIssue::warning(
+                    "Frequencies",
+                    format!("group '{key}' has no frequencies"),
+                ));
+            }
+            let mut repeated: Vec<f64> = Vec::new();
+            for (freq, _) in group.duplicates() {
+                if !repeated.iter().any(|x| (x - freq).abs() < SAME_FREQUENCY) {
+                    repeated.push(freq);
+                }
+            }
+            issues.extend(repeated.into_iter().map(|freq| {
+                ValidationIssue::warning(
+                    "Frequencies",
+                    format!("{} is listed more than once in '{key}'", format_hz(freq)),
+                )
+            }));
+            for freq in &group.vec {
+                if freq
+                    .range
+                    .is_some_and(|x| x.step <= 0.0 || x.stop < freq.freq)
+                {
+                    issues.push(ValidationIssue::error(
+                        "Frequencies",
+                        format!(
+                            "range starting at {} Hz in '{key}' has no valid steps",
+                            freq.freq
+                        ),
+                    ));
+                }
+                if let Some(len) = freq.range_too_long() {
+                    issues.push(ValidationIssue::error(
+                        "Frequencies",
+                        format!(
+                            "range starting at {} Hz in '{key}' covers {len} frequencies, more than {MAX_RANGE_POINTS}; only the first {MAX_RANGE_POINTS} are captured, so use a larger step",
+                            freq.freq
+                        ),
+                    ));
+                }
+            }
+        }
+        issues
+    }
+
+    /// Every tag in use, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .frequencies
+            .values()
+            .flat_map(|x| x.vec.iter().flat_map(|x| x.tags.iter().cloned()))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Whether `freq` passes the tag filter, always true with no filter set
+    pub fn matches_filter(&self, freq: &Frequency) -> bool {
+        self.tag_filter
+            .as_ref()
+            .is_none_or(|tag| freq.tags.contains(tag))
+    }
+
+    pub fn active_group(&self) -> Option<&String> {
+        self.active_group.as_ref()
+    }
+
+    pub fn update(&mut self, msg: Message) -> iced::Task<Message> {
+        let Message::Frequency(f) = msg else {
+            return iced::Task::none();
+        };
+        match f {
+            FrequencyMessage::AddGroup => {
+                let rand_name = utils::rand_name(|v| self.frequencies.contains_key(v));
+                let order = utils::next_order(&self.frequencies);
+                self.frequencies.insert(
+                    rand_name.clone(),
+                    FreqGroup {
+                        vec: Vec::new(),
+                        order,
+                    },
+                );
+                let id = focus::id(Field::GroupName, &rand_name);
+                self.active_group = Some(rand_name);
+                return focus::focus_and_select(id);
+            }
+
+            FrequencyMessage::GroupNameUpdated((key, new_key)) => {
+                if let hash_map::Entry::Occupied(entry) = self.frequencies.entry(key.clone()) {
+                    let (_, v) = entry.remove_entry();
+                    if self.collapsed.remove(&key) {
+                        self.collapsed.insert(new_key.clone());
+                    }
+                    for freq in &v.vec {
+                        self.index.insert(freq.id, new_key.clone());
+                    }
+                    self.frequencies.insert(new_key.clone(), v);
+                    self.active_group = Some(new_key);
+                }
+            }
+
+            FrequencyMessage::AddFreq(key) => {
+                let Some(group) = self.frequencies.get_mut(&key) else {
+                    return iced::Task::none();
+                };
+                let freq = Frequency::new(rand::random());
+                let id = focus::id(Field::FreqName, freq.id);
+                self.index.insert(freq.id, key.clone());
+                group.vec.push(freq);
+                self.active_group = Some(key);
+                return iced::widget::text_input::focus(id);
+            }
+
+            FrequencyMessage::PasteList(key) => {
+                return iced::clipboard::read().map(move |text| {
+                    Message::from(FrequencyMessage::PastedList((key.clone(), text)))
+                });
+            }
+
+            FrequencyMessage::PastedList((key, text)) => {
+                let Some(group) = self.frequencies.get_mut(&key) else {
+                    return iced::Task::none();
+                };
+                let (mut added, mut failed) = (0, 0);
+                for token in text
+                    .unwrap_or_default()
+                    .split(['\n', ','])
+                    .map(str::trim)
+                    .filter(|x| !x.is_empty())
+                {
+                    match parse_frequency(token) {
+                        Some(freq) => {
+                            let mut new = Frequency::new(rand::random());
+                            new.freq = freq;
+                            self.index.insert(new.id, key.clone());
+                            group.vec.push(new);
+                            added += 1;
+                        }
+                        None => failed += 1,
                     }
-                    self.frequencies
-                        .insert(rand_name.clone(), Vec::new().into());
-                    Message::None
-                }
-
-                FrequencyMessage::GroupNameUpdated((key, new_key)) => {
-                    if let hash_map::Entry::Occupied(entry) = self.frequencies.entry(key) {
-                        let (_, v) = entry.remove_entry();
-                        self.frequencies.insert(new_key, v);
-                        Message::None
-                    } else {
-                        Message::None
+                }
+                self.active_group = Some(key.clone());
+                return iced::Task::done(Message::Notice(format!(
+                    "Pasted {added} frequencies into '{key}', {failed} values could not be parsed"
+                )));
+            }
+
+            FrequencyMessage::ImportedCsv((key, text)) => {
+                let Some(group) = self.frequencies.get_mut(&key) else {
+                    return iced::Task::none();
+                };
+                let (freqs, skipped) = parse_csv(&text);
+                let added = freqs.len();
+                for freq in freqs {
+                    self.index.insert(freq.id, key.clone());
+                    group.vec.push(freq);
+                }
+                self.active_group = Some(key.clone());
+                return iced::Task::done(Message::Notice(format!(
+                    "Imported {added} frequencies into '{key}', skipped {skipped} malformed rows"
+                )));
+            }
+
+            FrequencyMessage::DelFreq(id) => {
+                if let Some((key, position)) = self.locate(id) {
+                    if let Some(group) = self.frequencies.get_mut(&key) {
+                        group.vec.remove(position);
                     }
+                    self.index.remove(&id);
                 }
+            }
 
-                FrequencyMessage::AddFreq(key) => {
-                    self.frequencies
-                        .get_mut(&key)
-                        .unwrap()
-                        .vec
-                        .push(Frequency::new(rand::random()));
-                    Message::None
+            FrequencyMessage::DelGroup(key) => {
+                if let Some(group) = self.frequencies.remove(&key) {
+                    for freq in group.vec {
+                        self.index.remove(&freq.id);
+                    }
                 }
+                self.collapsed.remove(&key);
+                self.found_runs.remove(&key);
+                self.renames.remove(&key);
+                if self.active_group.as_ref() == Some(&key) {
+                    self.active_group = None;
+                }
+            }
 
-                FrequencyMessage::DelFreq(id) => {
-                    self.frequencies
-                        .values_mut()
-                        .for_each(|x| {
-                            x.vec.retain(|x| x.id != id);
-                        });
-                    Message::None
+            FrequencyMessage::FreqAction(action) => {
+                let Some((key, position)) = self.locate(action.get_id()) else {
+                    return iced::Task::none();
+                };
+                if let Some(group) = self.frequencies.get_mut(&key) {
+                    group.vec[position].update(action);
                 }
+                self.active_group = Some(key);
+            }
 
-                FrequencyMessage::DelGroup(key) => {
-                    let _ = self.frequencies.remove(&key);
-                    Message::None
+            FrequencyMessage::ToggleGroup(key) => {
+                if !self.collapsed.remove(&key) {
+                    self.collapsed.insert(key);
                 }
+            }
 
-                FrequencyMessage::FreqAction(action) => {
-                    self.frequencies.values_mut().for_each(|x| x.vec.iter_mut().for_each(|x| x.update(action.clone())));
-                    Message::None
+            FrequencyMessage::InsertGroup((name, freqs)) => {
+                let name = if name.is_empty() {
+                    "group".to_string()
+                } else {
+                    name
+                };
+                let key = (1..)
+                    .map(|i| {
+                        if i == 1 {
+                            name.clone()
+                        } else {
+                            format!("{name} {i}")
+                        }
+                    })
+                    .find(|x| !self.frequencies.contains_key(x))
+                    .unwrap_or(name);
+                for freq in &freqs {
+                    self.index.insert(freq.id, key.clone());
                 }
+                let order = utils::next_order(&self.frequencies);
+                self.frequencies
+                    .insert(key.clone(), FreqGroup { vec: freqs, order });
+                self.active_group = Some(key);
+            }
+
+            FrequencyMessage::MoveGroupUp(key) => self.move_by(&key, true),
+
+            FrequencyMessage::MoveGroupDown(key) => self.move_by(&key, false),
+
+            FrequencyMessage::DelEmptyGroups => {
+                self.frequencies.retain(|_, group| !group.vec.is_empty());
+                self.collapsed
+                    .retain(|key| self.frequencies.contains_key(key));
+                if self
+                    .active_group
+                    .as_ref()
+                    .is_some_and(|key| !self.frequencies.contains_key(key))
+                {
+                    self.active_group = None;
+                }
+            }
+
+            FrequencyMessage::ExpandAll => {
+                self.collapsed.clear();
+            }
+
+            FrequencyMessage::CollapseAll => {
+                self.collapsed = self.frequencies.keys().cloned().collect();
+            }
+
+            FrequencyMessage::FilterTag(tag) => {
+                self.tag_filter = tag;
+            }
+
+            FrequencyMessage::Scrolled((key, offset)) => {
+                self.scroll.insert(key, offset);
+            }
+
+            // Handled by the config
+            FrequencyMessage::Priority(_) => (),
+
+            FrequencyMessage::StartRename(key) => {
+                self.renames.insert(key, Rename::default());
+            }
+
+            FrequencyMessage::RenameTemplate((key, template)) => {
+                if let Some(rename) = self.renames.get_mut(&key) {
+                    rename.template = template;
+                }
+            }
+
+            FrequencyMessage::RenameOnlyUnnamed((key, only_unnamed)) => {
+                if let Some(rename) = self.renames.get_mut(&key) {
+                    rename.only_unnamed = only_unnamed;
+                }
+            }
+
+            FrequencyMessage::CancelRename(key) => {
+                self.renames.remove(&key);
+            }
+
+            FrequencyMessage::ApplyRename(key) => {
+                let (Some(rename), Some(group)) =
+                    (self.renames.remove(&key), self.frequencies.get_mut(&key))
+                else {
+                    return iced::Task::none();
+                };
+                let names = Self::renamed(&rename, group);
+                let targets = group
+                    .vec
+                    .iter_mut()
+                    .filter(|x| !rename.only_unnamed || x.name.is_empty());
+                for (freq, (_, name)) in targets.zip(names) {
+                    freq.name = name;
+                }
+            }
+
+            FrequencyMessage::FindRanges(key) => {
+                let Some(group) = self.frequencies.get(&key) else {
+                    return iced::Task::none();
+                };
+                self.found_runs.insert(key, find_runs(&group.vec));
+            }
+
+            FrequencyMessage::DismissRanges(key) => {
+                self.found_runs.remove(&key);
+            }
+
+            FrequencyMessage::CollapseRanges(key) => {
+                let (Some(runs), Some(group)) =
+                    (self.found_runs.remove(&key), self.frequencies.get_mut(&key))
+                else {
+                    return iced::Task::none();
+                };
+                for run in runs {
+                    // The first member becomes the range, the rest are dropped
+                    let Some(first) = group.vec.iter_mut().find(|x| x.id == run.ids[0]) else {
+                        continue;
+                    };
+                    first.freq = run.start;
+                    first.range = Some(Range {
+                        stop: run.stop,
+                        step: run.step,
+                    });
+                    group.vec.retain(|x| !run.ids[1..].contains(&x.id));
+                    for id in &run.ids[1..] {
+                        self.index.remove(id);
+                    }
+                }
+            }
+
+            FrequencyMessage::ExpandRange(id) => {
+                let Some((key, i)) = self.locate(id) else {
+                    return iced::Task::none();
+                };
+                let Some(group) = self.frequencies.get_mut(&key) else {
+                    return iced::Task::none();
+                };
+                let range = group.vec.remove(i);
+                self.index.remove(&id);
+                let singles: Vec<Frequency> = range
+                    .points()
+                    .into_iter()
+                    .map(|freq| Frequency {
+                        freq,
+                        id: rand::random(),
+                        range: None,
+                        ..range.clone()
+                    })
+                    .collect();
+                for single in &singles {
+                    self.index.insert(single.id, key.clone());
+                }
+                group.vec.splice(i..i, singles);
             }
-        } else {
-            Message::None
         }
+        iced::Task::none()
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
-        let mut sorted_map = self.frequencies.iter().collect::<Vec<(&String, &FreqGroup)>>();
-        sorted_map.sort_by(|a,b| a.1.partial_cmp(b.1).unwrap());
+    /// A scrolling window over `rows` that only builds the rows in view,
+    /// standing in blank space for the rest
+    fn virtual_rows<'a>(
+        &self,
+        key: &str,
+        rows: Vec<&'a Frequency>,
+        duplicates: &[u64],
+        profile: Option<&HardwareProfile>,
+    ) -> iced::Element<'a, Message> {
+        let offset = self.scroll.get(key).copied().unwrap_or(0.0);
+        let visible = visible_rows(offset, WINDOW_HEIGHT, ROW_HEIGHT, rows.len());
+        let content = column![iced::widget::Space::with_height(
+            visible.start as f32 * ROW_HEIGHT
+        )]
+        .extend(rows[visible.clone()].iter().map(|x| {
+            iced::widget::container(x.view(duplicates.contains(&x.id), profile))
+                .height(ROW_HEIGHT)
+                .clip(true)
+                .into()
+        }))
+        .push(iced::widget::Space::with_height(
+            (rows.len() - visible.end) as f32 * ROW_HEIGHT,
+        ));
+        let key = key.to_string();
+        iced::widget::scrollable(content)
+            .height(WINDOW_HEIGHT)
+            .on_scroll(move |viewport| {
+                Message::from(FrequencyMessage::Scrolled((
+                    key.clone(),
+                    viewport.absolute_offset().y,
+                )))
+            })
+            .into()
+    }
+
+    /// Current and new name of each frequency a rename applies to, in order
+    fn renamed(rename: &Rename, group: &FreqGroup) -> Vec<(String, String)> {
+        group
+            .vec
+            .iter()
+            .filter(|x| !rename.only_unnamed || x.name.is_empty())
+            .enumerate()
+            .map(|(i, x)| (x.name.clone(), render_name(&rename.template, i + 1, x)))
+            .collect()
+    }
+
+    /// Template controls and a preview of the first few new names
+    fn rename_view<'a>(
+        key: &str,
+        rename: &'a Rename,
+        group: &FreqGroup,
+    ) -> iced::Element<'a, Message> {
+        const PREVIEW: usize = 5;
+        let renamed = Self::renamed(rename, group);
+        let key = key.to_string();
+        column![
+            iced::widget::text_input("template", &rename.template).on_input({
+                let key = key.clone();
+                move |c| Message::from(FrequencyMessage::RenameTemplate((key.clone(), c)))
+            }),
+            iced::widget::text("{index}, {index:3}, {mhz}, {hz}, {name}").size(12),
+            iced::widget::checkbox("only unnamed", rename.only_unnamed).on_toggle({
+                let key = key.clone();
+                move |c| Message::from(FrequencyMessage::RenameOnlyUnnamed((key.clone(), c)))
+            }),
+        ]
+        .extend(renamed.iter().take(PREVIEW).map(|(old, new)| {
+            let old = if old.is_empty() { "(unnamed)" } else { old };
+            iced::widget::text(format!("{old} -> {new}"))
+                .size(12)
+                .into()
+        }))
+        .push_maybe(
+            (renamed.len() > PREVIEW).then(|| {
+                iced::widget::text(format!("and {} more", renamed.len() - PREVIEW)).size(12)
+            }),
+        )
+        .push(
+            iced::widget::row![
+                iced::widget::button("Rename")
+                    .on_press(Message::from(FrequencyMessage::ApplyRename(key.clone()))),
+                iced::widget::button("Cancel")
+                    .on_press(Message::from(FrequencyMessage::CancelRename(key))),
+            ]
+            .spacing(8),
+        )
+        .spacing(4)
+        .into()
+    }
+
+    /// Tags used in a group; clicking one filters by it
+    fn tag_chips(group: &FreqGroup) -> iced::Element<'_, Message> {
+        let mut tags: Vec<&String> = group.vec.iter().flat_map(|x| &x.tags).collect();
+        tags.sort();
+        tags.dedup();
+        iced::widget::row(tags.into_iter().map(|tag| {
+            iced::widget::button(iced::widget::text(tag).size(12))
+                .style(iced::widget::button::secondary)
+                .padding([2, 6])
+                .on_press(Message::from(FrequencyMessage::FilterTag(Some(
+                    tag.clone(),
+                ))))
+                .into()
+        }))
+        .spacing(4)
+        .into()
+    }
+
+    /// Ranges found in a group, with the choice to collapse them
+    fn runs_view<'a>(key: &str, runs: &'a [Run]) -> iced::Element<'a, Message> {
+        if runs.is_empty() {
+            return iced::widget::row![
+                iced::widget::text("No evenly spaced frequencies found"),
+                iced::widget::button("OK").on_press(Message::from(
+                    FrequencyMessage::DismissRanges(key.to_string())
+                )),
+            ]
+            .spacing(8)
+            .into();
+        }
+        column![iced::widget::text(format!("Found {} ranges:", runs.len()))]
+            .extend(
+                runs.iter()
+                    .map(|run| iced::widget::text(run.to_string()).into()),
+            )
+            .push(
+                iced::widget::row![
+                    iced::widget::button("Collapse Into Ranges").on_press(Message::from(
+                        FrequencyMessage::CollapseRanges(key.to_string())
+                    )),
+                    iced::widget::button("Cancel").on_press(Message::from(
+                        FrequencyMessage::DismissRanges(key.to_string())
+                    )),
+                ]
+                .spacing(8),
+            )
+            .spacing(4)
+            .into()
+    }
+
+    /// `priorities` are the groups' capture priorities, shown in each header.
+    /// Frequencies `profile` can't tune to are flagged.
+    pub fn view<'a>(
+        &'a self,
+        priorities: &BTreeMap<String, u8>,
+        profile: Option<&HardwareProfile>,
+    ) -> iced::Element<'a, Message> {
+        let sorted_map = self.sorted();
         iced::widget::row![
-            iced::widget::button("Add Frequency Group")
-                .on_press(Message::from(FrequencyMessage::AddGroup)),
+            column![
+                iced::widget::button("Add Frequency Group")
+                    .on_press(Message::from(FrequencyMessage::AddGroup)),
+                iced::widget::button("Expand All")
+                    .on_press(Message::from(FrequencyMessage::ExpandAll)),
+                iced::widget::button("Collapse All")
+                    .on_press(Message::from(FrequencyMessage::CollapseAll)),
+                iced::widget::button("Delete Empty Groups")
+                    .on_press(Message::from(FrequencyMessage::DelEmptyGroups)),
+                iced::widget::pick_list(self.tags(), self.tag_filter.clone(), |c| {
+                    Message::from(FrequencyMessage::FilterTag(Some(c)))
+                })
+                .placeholder("Filter by tag"),
+                iced::widget::button("Clear Filter")
+                    .on_press(Message::from(FrequencyMessage::FilterTag(None))),
+            ]
+            .spacing(4),
         ]
         .extend(sorted_map.into_iter().map(|(k, v)| {
+            let collapsed = self.collapsed.contains(k);
             let col = column![
-                iced::widget::button("Delete").on_press(Message::from(FrequencyMessage::DelGroup(k.to_string()))),
-                iced::widget::text_input("group_name", k).on_input(|c| Message::from(
-                    FrequencyMessage::GroupNameUpdated((k.clone(), c))
-                )),
+                iced::widget::row![
+                    iced::widget::button(if collapsed { "Expand" } else { "Collapse" })
+                        .on_press(Message::from(FrequencyMessage::ToggleGroup(k.clone()))),
+                    iced::widget::button("Delete")
+                        .on_press(Message::from(FrequencyMessage::DelGroup(k.to_string()))),
+                    iced::widget::button("Up")
+                        .on_press(Message::from(FrequencyMessage::MoveGroupUp(k.clone()))),
+                    iced::widget::button("Down")
+                        .on_press(Message::from(FrequencyMessage::MoveGroupDown(k.clone()))),
+                    iced::widget::text("priority"),
+                    iced::widget::pick_list(
+                        PRIORITIES,
+                        Some(priorities.get(k).copied().unwrap_or_default()),
+                        |c| Message::from(FrequencyMessage::Priority((k.clone(), c))),
+                    ),
+                ]
+                .spacing(4),
+                iced::widget::text_input("group_name", k)
+                    .id(focus::id(Field::GroupName, k))
+                    .on_input(|c| Message::from(FrequencyMessage::GroupNameUpdated((
+                        k.clone(),
+                        c
+                    )))),
                 iced::widget::button("Add Frequency")
-                    .on_press(Message::from(FrequencyMessage::AddFreq(k.clone())))
-            ];
-            col.extend(v.vec.iter().map(|x| x.view()))
-                .spacing(8)
-                .into()
+                    .on_press(Message::from(FrequencyMessage::AddFreq(k.clone()))),
+                iced::widget::button("Paste List")
+                    .on_press(Message::from(FrequencyMessage::PasteList(k.clone()))),
+                iced::widget::button("Import CSV...")
+                    .on_press(Message::from(ToolbarMsg::ImportCsv(k.clone()))),
+                iced::widget::button("Find Ranges")
+                    .on_press(Message::from(FrequencyMessage::FindRanges(k.clone()))),
+                iced::widget::button("Rename All")
+                    .on_press(Message::from(FrequencyMessage::StartRename(k.clone()))),
+                iced::widget::button("Save as Template")
+                    .on_press(Message::from(templates::TemplateMsg::SaveGroup(k.clone()))),
+                Self::tag_chips(v),
+            ]
+            .push_maybe(
+                self.renames
+                    .get(k)
+                    .map(|rename| Self::rename_view(k, rename, v)),
+            )
+            .push_maybe(self.found_runs.get(k).map(|runs| Self::runs_view(k, runs)))
+            .push_maybe(
+                v.vec
+                    .is_empty()
+                    .then(|| iced::widget::text("No frequencies").style(validation::warning)),
+            );
+            if collapsed {
+                return col
+                    .push(iced::widget::text(format!("{} frequencies", v.vec.len())))
+                    .spacing(8)
+                    .into();
+            }
+            let rows: Vec<&Frequency> = v.vec.iter().filter(|x| self.matches_filter(x)).collect();
+            let duplicates = v.duplicate_ids();
+            if rows.len() > VIRTUALIZE_ABOVE {
+                return col
+                    .push(self.virtual_rows(k, rows, &duplicates, profile))
+                    .spacing(8)
+                    .into();
+            }
+            col.extend(
+                rows.into_iter()
+                    .map(|x| x.view(duplicates.contains(&x.id), profile)),
+            )
+            .spacing(8)
+            .into()
         }))
         .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranged(freq: f64, stop: f64, step: f64) -> Frequency {
+        Frequency {
+            freq,
+            range: Some(Range { stop, step }),
+            ..Frequency::new(0)
+        }
+    }
+
+    #[test]
+    fn parses_frequency_with_units() {
+        assert_eq!(parse_frequency("2.4G"), Some(2.4e9));
+        assert_eq!(parse_frequency("915m"), Some(915e6));
+        assert_eq!(parse_frequency("433.92 MHz"), Some(433.92e6));
+        assert_eq!(parse_frequency(" 12.5 kHz "), Some(12_500.0));
+        assert_eq!(parse_frequency("100hz"), Some(100.0));
+        assert_eq!(parse_frequency("2400000"), Some(2.4e6));
+    }
+
+    #[test]
+    fn refuses_bad_frequency() {
+        for text in [
+            "",
+            "MHz",
+            "G",
+            "abc",
+            "1.2.3G",
+            "433.92 MHz extra",
+            "inf",
+            "NaN GHz",
+            "1e308G",
+            "5 THz",
+        ] {
+            assert_eq!(parse_frequency(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn point_count_matches_points() {
+        for x in [
+            ranged(100.0, 200.0, 10.0),
+            ranged(100.0, 195.0, 10.0),
+            ranged(100.0, 50.0, 10.0),
+            Frequency::new(0),
+        ] {
+            assert_eq!(x.point_count(), x.points().len());
+        }
+        assert_eq!(ranged(100.0, 200.0, 10.0).point_count(), 11);
+        assert_eq!(ranged(100.0, 200.0, 10.0).span(), (100.0, 200.0));
+    }
+
+    #[test]
+    fn huge_range_is_capped() {
+        let x = ranged(1e6, 6e9, 1e-3);
+        assert_eq!(x.point_count(), MAX_RANGE_POINTS);
+        assert_eq!(x.points().len(), MAX_RANGE_POINTS);
+        assert!(x.range_too_long().is_some_and(|x| x > MAX_RANGE_POINTS));
+        assert!(ranged(1e6, 2e6, 1e3).range_too_long().is_none());
+    }
+
+    /// Asserts the index holds exactly the group of every frequency
+    fn assert_indexed(config: &FrequencyConfig) {
+        let scan: HashMap<u64, String> = config
+            .frequencies
+            .iter()
+            .flat_map(|(key, group)| group.vec.iter().map(move |x| (x.id, key.clone())))
+            .collect();
+        assert_eq!(config.index, scan);
+    }
+
+    #[test]
+    fn index_follows_every_change() {
+        let mut config: FrequencyConfig = serde_json::from_value(serde_json::json!({
+            "a": [
+                {"name": "x", "freq": 100.0, "description": ""},
+                {"name": "y", "freq": 200.0, "description": ""},
+                {"name": "z", "freq": 300.0, "description": ""},
+            ],
+            "b": [{"name": "w", "freq": 1000.0, "description": ""}],
+        }))
+        .unwrap();
+        config.reindex();
+        let apply = |config: &mut FrequencyConfig, msg: FrequencyMessage| {
+            let _ = config.update(msg.into());
+            assert_indexed(config);
+        };
+        let id = |config: &FrequencyConfig, key: &str, i: usize| config.frequencies[key].vec[i].id;
+
+        apply(&mut config, FrequencyMessage::AddFreq("b".to_string()));
+        apply(&mut config, FrequencyMessage::FindRanges("a".to_string()));
+        apply(
+            &mut config,
+            FrequencyMessage::CollapseRanges("a".to_string()),
+        );
+        assert_eq!(config.frequencies["a"].vec.len(), 1);
+        let range = id(&config, "a", 0);
+        apply(&mut config, FrequencyMessage::ExpandRange(range));
+        assert_eq!(config.frequencies["a"].vec.len(), 3);
+        apply(
+            &mut config,
+            FrequencyMessage::GroupNameUpdated(("b".to_string(), "c".to_string())),
+        );
+        apply(&mut config, FrequencyMessage::MoveGroupUp("c".to_string()));
+        let deleted = id(&config, "a", 1);
+        apply(&mut config, FrequencyMessage::DelFreq(deleted));
+        let renamed = id(&config, "a", 1);
+        apply(
+            &mut config,
+            FreqAction::NameUpdated((renamed, "moved".to_string())).into(),
+        );
+        apply(
+            &mut config,
+            FrequencyMessage::InsertGroup(("a".to_string(), vec![Frequency::new(7)])),
+        );
+        assert!(config.frequencies.contains_key("a 2"));
+        apply(&mut config, FrequencyMessage::DelGroup("a".to_string()));
+        apply(
+            &mut config,
+            FrequencyMessage::ImportedCsv(("c".to_string(), "n,5000,d".to_string())),
+        );
+        assert_eq!(config.frequencies["c"].vec.len(), 3);
+    }
+
+    #[test]
+    fn visible_rows_cover_window_with_overscan() {
+        // 10 rows of 30 fit in 300
+        assert_eq!(visible_rows(0.0, 300.0, 30.0, 1000), 0..12);
+        assert_eq!(visible_rows(3000.0, 300.0, 30.0, 1000), 98..112);
+        // Partly scrolled rows at either edge are included
+        assert_eq!(visible_rows(3015.0, 300.0, 30.0, 1000), 98..113);
+        // Clamped to the list, even when scrolled past it or above it
+        assert_eq!(visible_rows(29_900.0, 300.0, 30.0, 1000), 994..1000);
+        assert_eq!(visible_rows(1e9, 300.0, 30.0, 1000), 1000..1000);
+        assert_eq!(visible_rows(-50.0, 300.0, 30.0, 1000), 0..12);
+        assert_eq!(visible_rows(0.0, 300.0, 30.0, 5), 0..5);
+        assert_eq!(visible_rows(0.0, 300.0, 30.0, 0), 0..0);
+    }
+
+    #[test]
+    fn visible_rows_are_bounded_however_long_the_list() {
+        for len in [100, 10_000, 1_000_000] {
+            for offset in [0.0, 1234.5, 29_000.0] {
+                assert!(visible_rows(offset, 300.0, 30.0, len).len() <= 10 + 2 * OVERSCAN + 1);
+            }
+        }
+    }
+
+    fn single(freq: f64) -> Frequency {
+        Frequency {
+            freq,
+            ..Frequency::new(0)
+        }
+    }
+
+    /// The frequencies as a group, with ids 1, 2, ... in order
+    fn group(freqs: Vec<Frequency>) -> FreqGroup {
+        let vec = freqs
+            .into_iter()
+            .zip(1..)
+            .map(|(freq, id)| Frequency { id, ..freq })
+            .collect();
+        FreqGroup { vec, order: 0 }
+    }
+
+    #[test]
+    fn exact_duplicates_are_found() {
+        let freqs = group(vec![
+            single(915e6),
+            single(433.92e6),
+            single(915e6 + 4e-4),
+            single(915e6),
+        ]);
+        assert_eq!(freqs.duplicate_ids(), [1, 3, 4]);
+    }
+
+    #[test]
+    fn duplicates_within_ranges_are_found() {
+        // 433.92 MHz is a step of the range; 433.925 MHz falls between steps
+        let freqs = group(vec![
+            ranged(433e6, 434e6, 20e3),
+            single(433.92e6),
+            single(433.925e6),
+            single(868e6),
+        ]);
+        assert_eq!(freqs.duplicate_ids(), [1, 2]);
+        let overlapping = group(vec![ranged(100e6, 110e6, 1e6), ranged(105e6, 120e6, 5e6)]);
+        assert_eq!(overlapping.duplicate_ids(), [1, 2]);
+    }
+
+    #[test]
+    fn near_frequencies_are_not_duplicates() {
+        let freqs = group(vec![
+            single(915e6),
+            single(915e6 + 1.0),
+            single(0.0),
+            single(0.0),
+            ranged(100e6, 100.0001e6, 50.0),
+        ]);
+        assert!(freqs.duplicate_ids().is_empty());
+    }
+}
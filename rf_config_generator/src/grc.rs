@@ -0,0 +1,257 @@
+use serde_json::{Value, json};
+
+use crate::{
+    Message,
+    collection_modes::CollectionMode,
+    frequency::{self, Frequency},
+};
+
+/// GNU Radio Companion flowgraph capturing `freq` with the collection mode
+/// `mode_name`: a UHD source tuned to it, recording `duration` seconds to
+/// a file and showing the spectrum while it runs. Frequency, sample rate,
+/// gain, duration and output file are flowgraph parameters, so they can
+/// be changed in GRC or on the generated script's command line. A mode
+/// sweeping gains starts at the first gain of the sweep.
+pub fn config_to_grc(freq: &Frequency, mode_name: &str, mode: &CollectionMode) -> String {
+    let gain = mode.gains().first().copied().unwrap_or(mode.gain);
+    let file = crate::utils::sanitize_name(&format!("{}_{mode_name}.dat", freq.name));
+    let source = json!({
+        "type": "fc32",
+        "otw": "",
+        "stream_args": "",
+        "dev_addr": "\"\"",
+        "num_mboards": "1",
+        "nchan": "1",
+        "samp_rate": "samp_rate",
+        "center_freq0": "freq",
+        "gain0": "gain",
+        "ant0": mode.antenna.as_deref().map(|x| format!("\"{x}\"")).unwrap_or_default(),
+    });
+    let head = json!({
+        "type": "complex",
+        "num_items": "int(samp_rate * duration)",
+        "vlen": "1",
+    });
+    let sink = json!({
+        "type": "complex",
+        "file": "rf_fn",
+        "append": "False",
+        "unbuffered": "False",
+        "vlen": "1",
+    });
+    let spectrum = json!({
+        "type": "complex",
+        "name": format!("\"{} - {mode_name}\"", freq.name.replace('"', "'")),
+        "fc": "freq",
+        "bw": "samp_rate",
+        "fftsize": "1024",
+        "nconnections": "1",
+        "ymax": "10",
+        "ymin": "-140",
+    });
+    let flowgraph = json!({
+        "options": {
+            "parameters": {
+                "id": identifier(&format!("capture_{}_{mode_name}", freq.name)),
+                "title": format!("{} ({}) with {mode_name}", freq.name, frequency::format_hz(freq.freq)),
+                "generate_options": "qt_gui",
+                "output_language": "python",
+                "run": "True",
+                "run_options": "prompt",
+            },
+            "states": states([8, 8]),
+        },
+        "blocks": [
+            parameter("freq", "eng_float", &freq.freq.to_string(), [288, 8]),
+            parameter("samp_rate", "eng_float", &mode.sample_rate.to_string(), [400, 8]),
+            parameter("gain", "eng_float", &gain.to_string(), [512, 8]),
+            parameter("duration", "eng_float", &mode.duration.to_string(), [624, 8]),
+            parameter("rf_fn", "str", &format!("\"{file}\""), [736, 8]),
+            block("uhd_usrp_source_0", "uhd_usrp_source", source, [120, 200]),
+            block("blocks_head_0", "blocks_head", head, [480, 180]),
+            block("blocks_file_sink_0", "blocks_file_sink", sink, [720, 160]),
+            block("qtgui_freq_sink_x_0", "qtgui_freq_sink_x", spectrum, [480, 320]),
+        ],
+        "connections": [
+            ["uhd_usrp_source_0", "0", "blocks_head_0", "0"],
+            ["blocks_head_0", "0", "blocks_file_sink_0", "0"],
+            ["uhd_usrp_source_0", "0", "qtgui_freq_sink_x_0", "0"],
+        ],
+        "metadata": { "file_format": 1 },
+    });
+    serde_yaml::to_string(&flowgraph).unwrap_or_default()
+}
+
+/// `name` as a Python identifier, which GRC requires of flowgraph ids
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn states(coordinate: [u32; 2]) -> Value {
+    json!({
+        "bus_sink": false,
+        "bus_source": false,
+        "bus_structure": null,
+        "coordinate": coordinate,
+        "rotation": 0,
+        "state": "enabled",
+    })
+}
+
+/// Block with only the parameters that differ from GRC's defaults, which
+/// GRC fills in when it loads the file
+fn block(name: &str, id: &str, parameters: Value, coordinate: [u32; 2]) -> Value {
+    json!({ "name": name, "id": id, "parameters": parameters, "states": states(coordinate) })
+}
+
+fn parameter(name: &str, kind: &str, value: &str, coordinate: [u32; 2]) -> Value {
+    let parameters =
+        json!({ "type": kind, "value": value, "hide": "none", "label": "", "short_id": "" });
+    block(name, "parameter", parameters, coordinate)
+}
+
+/// A frequency offered for a flowgraph, by its group and position there
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyPick {
+    pub group: String,
+    pub index: usize,
+    label: String,
+}
+
+impl FrequencyPick {
+    pub fn new(group: &str, index: usize, freq: &Frequency) -> Self {
+        Self {
+            group: group.to_string(),
+            index,
+            label: format!(
+                "{group}: {} ({})",
+                freq.name,
+                frequency::format_hz(freq.freq)
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for FrequencyPick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Choosing the frequency and mode a flowgraph or SigMF metadata is
+/// generated for
+#[derive(Debug, Clone)]
+pub enum FlowgraphMsg {
+    Open,
+    Frequency(FrequencyPick),
+    Mode(String),
+    Export,
+    ExportSigmf,
+    Close,
+}
+
+impl From<FlowgraphMsg> for Message {
+    fn from(value: FlowgraphMsg) -> Self {
+        Message::Flowgraph(value)
+    }
+}
+
+/// Inputs of the flowgraph and SigMF exports, shown while `open`
+#[derive(Debug, Default)]
+pub struct FlowgraphPanel {
+    pub open: bool,
+    pub frequency: Option<FrequencyPick>,
+    pub mode: Option<String>,
+}
+
+impl FlowgraphPanel {
+    pub fn view<'a>(
+        &'a self,
+        frequencies: Vec<FrequencyPick>,
+        modes: Vec<String>,
+    ) -> iced::Element<'a, Message> {
+        let ready = self.frequency.is_some() && self.mode.is_some();
+        iced::widget::row![
+            iced::widget::text("Flowgraph or SigMF metadata for"),
+            iced::widget::pick_list(frequencies, self.frequency.clone(), |c| {
+                FlowgraphMsg::Frequency(c).into()
+            })
+            .placeholder("choose frequency"),
+            iced::widget::text("with"),
+            iced::widget::pick_list(modes, self.mode.clone(), |c| FlowgraphMsg::Mode(c).into())
+                .placeholder("choose collection mode"),
+            iced::widget::button("Export Flowgraph...")
+                .on_press_maybe(ready.then_some(FlowgraphMsg::Export.into())),
+            iced::widget::button("Export SigMF...")
+                .on_press_maybe(ready.then_some(FlowgraphMsg::ExportSigmf.into())),
+            iced::widget::button("Cancel").on_press(FlowgraphMsg::Close.into()),
+        ]
+        .spacing(10)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Value of the parameter block `name`, parsed as GRC evaluates it
+    fn parameter_value(flowgraph: &serde_yaml::Value, name: &str) -> f64 {
+        let block = flowgraph["blocks"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .find(|x| x["name"].as_str() == Some(name))
+            .unwrap_or_else(|| panic!("no {name} block"));
+        assert_eq!(block["id"].as_str(), Some("parameter"));
+        block["parameters"]["value"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn flowgraph_carries_frequency_rate_and_gain() {
+        let mut freq = Frequency::new(0);
+        freq.name = "ism \"433\"".to_string();
+        freq.freq = 433.92e6;
+        let mut mode = CollectionMode::default();
+        mode.sample_rate = 2.4e6;
+        mode.duration = 30.0;
+        mode.gain = 40.0;
+        let flowgraph: serde_yaml::Value =
+            serde_yaml::from_str(&config_to_grc(&freq, "survey", &mode)).unwrap();
+        assert_eq!(parameter_value(&flowgraph, "freq"), 433.92e6);
+        assert_eq!(parameter_value(&flowgraph, "samp_rate"), 2.4e6);
+        assert_eq!(parameter_value(&flowgraph, "gain"), 40.0);
+        assert_eq!(parameter_value(&flowgraph, "duration"), 30.0);
+        let id = flowgraph["options"]["parameters"]["id"].as_str().unwrap();
+        assert!(
+            id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "{id}"
+        );
+    }
+
+    #[test]
+    fn sweep_starts_at_first_gain() {
+        let mut mode = CollectionMode::default();
+        mode.gain = 40.0;
+        mode.gain_sweep = Some(crate::collection_modes::GainSweep {
+            start: 10.0,
+            stop: 30.0,
+            step: 10.0,
+        });
+        let flowgraph: serde_yaml::Value =
+            serde_yaml::from_str(&config_to_grc(&Frequency::new(0), "sweep", &mode)).unwrap();
+        assert_eq!(parameter_value(&flowgraph, "gain"), 10.0);
+    }
+}
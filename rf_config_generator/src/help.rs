@@ -0,0 +1,107 @@
+/// Sections the help sidebar can describe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    Summary,
+    Frequencies,
+    CaptureSettings,
+    CollectionModes,
+    Scheduling,
+    Notes,
+}
+
+const SUMMARY: &str = "The summary counts what the config holds and how many individual \
+captures it expands to: every enabled frequency (each step of a range) under every \
+collection mode, once per gain, with the total recording time. The sample rates, \
+durations and gains listed in Capture Settings are only choices for the modes, so they \
+don't multiply the count themselves. Past 1000 captures it turns amber and a warning is listed. The list below it shows problems that should be fixed before \
+the config is used.";
+
+const FREQUENCIES: &str = "Frequencies are the center frequencies the SDR tunes to, in Hz \
+(values like 433.92M, 433.92 MHz or 2.4G are accepted when typing or pasting; press Enter \
+to see the value in Hz). They are organised into named \
+groups, e.g. one per band; Up and Down move a group, and the file lists groups in that \
+order. A frequency listed twice in one group, or also a step of a range there, is highlighted. \
+\"Import CSV...\" appends rows of name,freq,description to a group, skipping rows \
+whose frequency can't be read. Unchecking \"enabled\" keeps a frequency in the file but \
+skips it when capturing. \"Save as Template\" stores a group relative to its first \
+frequency so the same layout can be instantiated around any base frequency.";
+
+const CAPTURE_SETTINGS: &str = "Capture settings list the values the SDR may be set to. \
+Sample rate is how many samples per second are recorded (Hz); it sets the bandwidth \
+captured around each frequency. With a hardware profile picked, sample rates and \
+frequencies outside what that SDR can do are outlined in red and listed as warnings. Duration is how long each capture lasts, in seconds. \
+Gain is the receiver amplification in dB: too low buries weak signals in noise, too \
+high saturates on strong ones. \"Generate Range\" fills a list from a start, stop and \
+step (a negative step counts down), replacing it unless \"append\" is ticked. \
+Compression is applied to the recorded IQ files: zstd takes levels 1-22, gzip 0-9 and lz4 0-12 (3 and up is HC); none writes them as is.";
+
+const COLLECTION_MODES: &str = "A collection mode is a named recipe for one capture: a \
+sample rate, duration and gain (or a sweep of gains) plus an optional antenna port. \
+The sample rate, duration and gain are picked from those listed in Capture Settings. \
+Every enabled frequency is captured once with each collection mode. \"Duplicate\" copies \
+a mode to <name>_copy, right below it, as a starting point for a variant.";
+
+const SCHEDULING: &str = "Schedule slots are named time windows in which captures run. \
+A slot repeats daily, weekly on the days picked, or runs once. Repeating slots take times \
+of day (HH:MM); a one-off slot takes ISO-8601 dates and times such as 2024-05-01T08:00. \
+A field that is neither is outlined in red. A window \
+whose end is before its start runs past midnight and has to be confirmed. A slot may be \
+limited to one frequency group and one collection mode; left on \"any\" it captures all \
+of them.";
+
+const NOTES: &str = "Notes are free text saved with the config, for recording why it \
+exists: the campaign, why these frequencies, anything the next person to open it should \
+know. They don't affect capturing.";
+
+pub fn text(topic: Topic) -> &'static str {
+    match topic {
+        Topic::Summary => SUMMARY,
+        Topic::Frequencies => FREQUENCIES,
+        Topic::CaptureSettings => CAPTURE_SETTINGS,
+        Topic::CollectionModes => COLLECTION_MODES,
+        Topic::Scheduling => SCHEDULING,
+        Topic::Notes => NOTES,
+    }
+}
+
+pub fn title(topic: Topic) -> &'static str {
+    match topic {
+        Topic::Summary => "Summary",
+        Topic::Frequencies => "Frequencies",
+        Topic::CaptureSettings => "Capture Settings",
+        Topic::CollectionModes => "Collection Modes",
+        Topic::Scheduling => "Scheduling",
+        Topic::Notes => "Notes",
+    }
+}
+
+/// Wraps a section so hovering it shows its help
+pub fn section<'a>(
+    content: impl Into<iced::Element<'a, crate::Message>>,
+    topic: Topic,
+) -> iced::Element<'a, crate::Message> {
+    iced::widget::mouse_area(content)
+        .on_enter(crate::Message::HelpTopic(topic))
+        .into()
+}
+
+/// Sidebar explaining `topic`, or pointing at how to pick one
+pub fn view<'a>(topic: Option<Topic>) -> iced::Element<'a, crate::Message> {
+    let body: iced::Element<'a, crate::Message> = match topic {
+        Some(topic) => iced::widget::column![
+            iced::widget::text(title(topic)).size(20),
+            iced::widget::text(text(topic)),
+        ]
+        .spacing(10)
+        .into(),
+        None => iced::widget::text("Hover over a section to see what it does.").into(),
+    };
+    iced::widget::column![
+        iced::widget::button("Close Help")
+            .on_press(crate::settings::SettingsMsg::ShowHelp(false).into()),
+        body,
+    ]
+    .spacing(10)
+    .width(300)
+    .into()
+}
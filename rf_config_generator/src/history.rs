@@ -0,0 +1,223 @@
+use crate::{
+    Message,
+    capture_settings::CaptureSettingsMsg,
+    collection_modes::{CollectionModesMsg, CollectionMsg},
+    config::Config,
+    frequency::{FreqAction, FrequencyMessage},
+    scheduling::{ChangeMsg, SchedulingMsg},
+};
+
+/// Oldest steps are dropped past this many
+const MAX_STEPS: usize = 100;
+
+/// A config with the generation it had, see `App::generation`
+pub type Snapshot = (Config, u64);
+
+/// Undo and redo stacks of whole-config snapshots. Every message that
+/// changes the config is one step, so bulk operations undo in one go;
+/// consecutive keystrokes into the same field are merged into one step.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    // Field the newest step was typing into
+    last_edit: Option<String>,
+}
+
+impl History {
+    /// Records `before`, the config as it was before a change made by a
+    /// message with the given [`edit_target`]
+    pub fn record(&mut self, before: Snapshot, edit: Option<String>) {
+        self.redo.clear();
+        if edit.is_some() && edit == self.last_edit {
+            return;
+        }
+        self.last_edit = edit;
+        self.undo.push(before);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The config to go back to, given the current one
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.last_edit = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.last_edit = None;
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Whether a message only changes how the config is shown, or a draft
+/// that isn't saved, so it can skip undo and change tracking
+pub fn view_only(msg: &Message) -> bool {
+    match msg {
+        Message::Frequency(msg) => matches!(
+            msg,
+            FrequencyMessage::ToggleGroup(_)
+                | FrequencyMessage::ExpandAll
+                | FrequencyMessage::CollapseAll
+                | FrequencyMessage::FindRanges(_)
+                | FrequencyMessage::DismissRanges(_)
+                | FrequencyMessage::FilterTag(_)
+                | FrequencyMessage::StartRename(_)
+                | FrequencyMessage::RenameTemplate(_)
+                | FrequencyMessage::RenameOnlyUnnamed(_)
+                | FrequencyMessage::CancelRename(_)
+                | FrequencyMessage::Scrolled(_)
+                | FrequencyMessage::PasteList(_)
+                | FrequencyMessage::FreqAction(
+                    FreqAction::TagDraftUpdated(_) | FreqAction::FreqSubmitted(_)
+                )
+        ),
+        Message::CaptureSettingsMsg(msg) => {
+            matches!(
+                msg,
+                CaptureSettingsMsg::RangeInput(_) | CaptureSettingsMsg::RangeAppend(_)
+            )
+        }
+        Message::CollectionModes(msg) => matches!(
+            msg,
+            CollectionModesMsg::Toggle(_)
+                | CollectionModesMsg::ExpandAll
+                | CollectionModesMsg::CollapseAll
+        ),
+        _ => false,
+    }
+}
+
+/// The text field a message types into, if it is a keystroke. Keystrokes
+/// into the same field in a row share one undo step.
+pub fn edit_target(msg: &Message) -> Option<String> {
+    match msg {
+        Message::Frequency(FrequencyMessage::GroupNameUpdated(_)) => Some("group name".to_string()),
+        Message::Frequency(FrequencyMessage::FreqAction(action)) => match action {
+            FreqAction::NameUpdated((id, _)) => Some(format!("freq name {id}")),
+            FreqAction::FreqUpdated((id, _)) => Some(format!("freq value {id}")),
+            FreqAction::DescUpdated((id, _)) => Some(format!("freq description {id}")),
+            _ => None,
+        },
+        Message::CaptureSettingsMsg(msg) => match msg {
+            CaptureSettingsMsg::UpdateSampleRate((_, i)) => Some(format!("sample rate {i}")),
+            CaptureSettingsMsg::UpdateDuration((_, i)) => Some(format!("duration {i}")),
+            CaptureSettingsMsg::UpdateGain((_, i)) => Some(format!("gain {i}")),
+            CaptureSettingsMsg::ChangeCompression(_) => Some("compression".to_string()),
+            CaptureSettingsMsg::ChangeCompressionLevel(_) => Some("compression level".to_string()),
+            _ => None,
+        },
+        Message::CollectionModes(CollectionModesMsg::Change((key, msg))) => match msg {
+            // Renaming changes the key, so every rename keystroke matches
+            CollectionMsg::ChangeName(_) => Some("mode name".to_string()),
+            CollectionMsg::ChangeSampleRate(_) => Some(format!("mode sample rate {key}")),
+            CollectionMsg::ChangeDuration(_) => Some(format!("mode duration {key}")),
+            CollectionMsg::ChangeGain(_) => Some(format!("mode gain {key}")),
+            CollectionMsg::ChangeSweepStart(_) => Some(format!("sweep start {key}")),
+            CollectionMsg::ChangeSweepStop(_) => Some(format!("sweep stop {key}")),
+            CollectionMsg::ChangeSweepStep(_) => Some(format!("sweep step {key}")),
+            _ => None,
+        },
+        Message::Notes(_) => Some("notes".to_string()),
+        Message::Scheduling(SchedulingMsg::Change(id, msg)) => match msg {
+            ChangeMsg::Name(_) => Some(format!("slot name {id}")),
+            ChangeMsg::Start(_) => Some(format!("slot start {id}")),
+            ChangeMsg::End(_) => Some(format!("slot end {id}")),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_settings::{RangeField, ValueList};
+
+    fn app() -> crate::App {
+        let config = serde_json::from_value(serde_json::json!({
+            "frequencies": {
+                "ism": [
+                    {"name": "ism_315", "freq": 315e6, "description": ""},
+                    {"name": "ism_433", "freq": 433.92e6, "description": ""},
+                    {"name": "ism_868", "freq": 868e6, "description": ""},
+                ],
+            },
+            "capture_settings": {
+                "sample_rates": [2e6], "durations": [10], "gains": [30],
+                "compression": "", "compression_level": 0,
+            },
+            "collection_modes": {"survey": {"sample_rate": 2e6, "duration": 10, "gain": 30}},
+            "scheduling": {"time_slots": [{"name": "night", "start": "01:00", "end": "02:00", "group": "ism"}]},
+        }))
+        .unwrap();
+        crate::App {
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn saved(app: &crate::App) -> serde_json::Value {
+        serde_json::to_value(&app.config).unwrap()
+    }
+
+    /// Applies `msg` as one edit, then checks one undo brings back
+    /// everything it changed and one redo the whole of it again
+    fn undoes_in_one_step(mut app: crate::App, msg: Message) {
+        let before = saved(&app);
+        let _ = app.update(msg);
+        let after = saved(&app);
+        assert_ne!(after, before);
+
+        let _ = app.update(Message::Undo);
+        assert_eq!(saved(&app), before);
+        assert!(!app.history.can_undo());
+        let _ = app.update(Message::Redo);
+        assert_eq!(saved(&app), after);
+        assert!(!app.history.can_redo());
+    }
+
+    #[test]
+    fn bulk_delete_is_one_step() {
+        // The group's three frequencies and the slot's reference to it
+        undoes_in_one_step(app(), Message::DeleteGroup(("ism".to_string(), true)));
+    }
+
+    #[test]
+    fn sweep_generation_is_one_step() {
+        let mut app = app();
+        for (field, text) in [
+            (RangeField::Start, "1e6"),
+            (RangeField::Stop, "5e6"),
+            (RangeField::Step, "1e6"),
+        ] {
+            let _ = app.update(
+                CaptureSettingsMsg::RangeInput((ValueList::SampleRates, field, text.to_string()))
+                    .into(),
+            );
+        }
+        // Typing the range is a draft, not a step of its own
+        assert!(!app.history.can_undo());
+        undoes_in_one_step(
+            app,
+            CaptureSettingsMsg::GenerateRange(ValueList::SampleRates).into(),
+        );
+    }
+}
@@ -0,0 +1,999 @@
+use crate::{config::Config, toolbar::ToolbarMsg};
+
+mod capture_settings;
+pub mod cli;
+mod collection_modes;
+pub mod config;
+mod device;
+mod file_format;
+mod focus;
+mod frequency;
+mod grc;
+mod help;
+mod history;
+mod migrate;
+mod scheduling;
+mod script;
+mod settings;
+mod sigmf;
+mod tasks;
+mod templates;
+mod toolbar;
+mod utils;
+pub mod validation;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    None,
+    Toolbar(ToolbarMsg),
+    Frequency(frequency::FrequencyMessage),
+    CaptureSettingsMsg(capture_settings::CaptureSettingsMsg),
+    CollectionModes(collection_modes::CollectionModesMsg),
+    Scheduling(scheduling::SchedulingMsg),
+    Device(device::DeviceMsg),
+    Shortcut(Shortcut),
+    // Result of looking up the focused field, used to step Tab focus
+    FocusStep(bool, Option<iced::advanced::widget::Id>),
+    DismissWarning,
+    // Informational message shown until dismissed
+    Notice(String),
+    DismissNotice,
+    Settings(settings::SettingsMsg),
+    Preset(capture_settings::PresetMsg),
+    Template(templates::TemplateMsg),
+    Flowgraph(grc::FlowgraphMsg),
+    ToggleSettings,
+    // Swaps the editor for the read-only list of capture tasks and back
+    TogglePreview,
+    // Section under the pointer, shown in the help sidebar
+    HelpTopic(help::Topic),
+    // Scrolls back to the validation list at the top of the config
+    ShowValidation,
+    // Sent once a revalidation's or the status bar error's deadline has
+    // passed, see `wake_after`
+    Tick(std::time::Instant),
+    DismissError,
+    Undo,
+    Redo,
+    // Keystroke or cursor movement in the notes editor
+    NotesAction(iced::widget::text_editor::Action),
+    // New text of the notes
+    Notes(String),
+    // Lifts read-only mode on a config from a newer version
+    AllowEditing,
+    // Deletes a frequency group, also clearing references to it when true
+    DeleteGroup((String, bool)),
+    // Adds a SigMF recording's frequencies as a group of the name given,
+    // and its sample rate and gain to Capture Settings
+    ImportSigmf((String, Box<sigmf::Recording>)),
+    // Answer to the delete-group confirmation: delete with or without
+    // clearing references, or None to cancel
+    ConfirmDeleteGroup(Option<bool>),
+}
+
+/// How long typing has to pause before validation reruns
+const VALIDATION_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// How long a failed file operation stays in the status bar
+const ERROR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// Id of the scrollable holding the config sections
+fn config_scroll_id() -> iced::widget::scrollable::Id {
+    iced::widget::scrollable::Id::new("config")
+}
+
+/// Keyboard shortcuts. These are only delivered when no widget captured the
+/// key press, so they never fire while a text input has focus (press Escape
+/// to leave a field first). Adding a frequency or group only works while the
+/// frequency section is the one that last took input.
+#[derive(Debug, Clone, Copy)]
+pub enum Shortcut {
+    /// Ctrl+F: add a frequency to the last focused group
+    AddFrequency,
+    /// Ctrl+G: add a frequency group
+    AddGroup,
+    /// Tab / Shift+Tab: move to the next / previous field
+    FocusNext,
+    FocusPrevious,
+    /// Ctrl+Z: undo the last change
+    Undo,
+    /// Ctrl+Shift+Z or Ctrl+Y: redo
+    Redo,
+}
+
+#[derive(Default)]
+struct App {
+    toolbar: toolbar::Toolbar,
+    config: config::Config,
+    warning: Option<String>,
+    notice: Option<String>,
+    settings: settings::AppSettings,
+    show_settings: bool,
+    help_topic: Option<help::Topic>,
+    // Loaded file awaiting confirmation, with why it looks wrong
+    pending_open: Option<(Box<Config>, std::path::PathBuf, String)>,
+    // Revert asked for, waiting on the user to confirm dropping changes
+    confirm_revert: bool,
+    // New or Open asked for with unsaved changes, run once the user
+    // confirms dropping them
+    pending_discard: Option<ToolbarMsg>,
+    // Save was asked for while the config has validation issues, which
+    // are listed until saved anyway (warnings only) or dismissed
+    confirm_save: bool,
+    // The config came from a newer format version, so edits are refused
+    // until the user chooses to edit anyway
+    read_only: bool,
+    // Group whose deletion waits on confirmation, with what refers to it
+    pending_delete: Option<(String, Vec<String>)>,
+    // File the config was last opened from or saved to
+    path: Option<std::path::PathBuf>,
+    // Changes every time an edit changes the config, and goes back with
+    // undo; compared with `clean_generation`, the one it had when last
+    // opened, saved or created, to tell whether there are unsaved changes
+    generation: u64,
+    clean_generation: u64,
+    // Last generation handed out, so each one is only used once
+    last_generation: u64,
+    history: history::History,
+    presets: capture_settings::PresetPanel,
+    templates: templates::TemplatePanel,
+    flowgraph: grc::FlowgraphPanel,
+    // Showing the expanded capture tasks instead of the editor
+    preview_tasks: bool,
+    // Editor state of the config's notes, reloaded whenever the config
+    // is replaced
+    notes: iced::widget::text_editor::Content,
+    // A file dialog, read or write is running in the background
+    in_progress: bool,
+    // The frequency section took the last input, so Ctrl+F and Ctrl+G
+    // apply; input anywhere else turns them off
+    frequencies_focused: bool,
+    // Last validation results, shown by the panel and the status bar.
+    // Typing defers the rerun until `validate_after`; every other change
+    // reruns it straight away.
+    validation: Vec<validation::ValidationIssue>,
+    validate_after: Option<std::time::Instant>,
+    // Last failed file operation, shown in the status bar until the time
+    // given or until dismissed
+    error: Option<(String, std::time::Instant)>,
+}
+
+impl App {
+    fn new() -> Self {
+        let mut app = Self {
+            settings: settings::AppSettings::load(),
+            ..Self::default()
+        };
+        app.mark_clean();
+        app.revalidate();
+        app
+    }
+
+    fn revalidate(&mut self) {
+        self.validation = self.config.validate();
+        self.validate_after = None;
+    }
+
+    fn load_notes(&mut self) {
+        self.notes = iced::widget::text_editor::Content::with_text(self.config.notes());
+    }
+
+    fn mark_clean(&mut self) {
+        self.clean_generation = self.generation;
+        self.confirm_save = false;
+    }
+
+    /// Applies a message to the config, recording an undo step if it
+    /// changed anything that is saved
+    fn edit(&mut self, message: Message) -> iced::Task<Message> {
+        let target = history::edit_target(&message);
+        let before = self.config.clone();
+        let task = self.config.update(message);
+        if before != self.config {
+            if self.read_only {
+                self.config = before;
+                return iced::Task::done(Message::Notice(
+                    "This config is read-only because it comes from a newer version; choose Edit Anyway to change it".to_string(),
+                ));
+            }
+            let typing = target.is_some();
+            self.history.record((before, self.generation), target);
+            self.last_generation += 1;
+            self.generation = self.last_generation;
+            if typing {
+                self.validate_after = Some(std::time::Instant::now() + VALIDATION_DEBOUNCE);
+                return iced::Task::batch([task, wake_after(VALIDATION_DEBOUNCE)]);
+            }
+            self.revalidate();
+        }
+        task
+    }
+
+    fn dirty(&self) -> bool {
+        self.generation != self.clean_generation
+    }
+
+    /// Swaps in a config from elsewhere, e.g. a file, as a new generation
+    fn replace_config(&mut self, config: Config) {
+        self.config = config;
+        self.last_generation += 1;
+        self.generation = self.last_generation;
+    }
+
+    /// Window title: the file name, starred when there are unsaved changes
+    fn title(&self) -> String {
+        let name = self
+            .path
+            .as_ref()
+            .and_then(|x| x.file_name())
+            .map_or("Untitled".to_string(), |x| x.to_string_lossy().into_owned());
+        let star = if self.dirty() { "*" } else { "" };
+        format!("{star}{name} - RF Config Generator")
+    }
+
+    fn has_errors(&self) -> bool {
+        self.validation
+            .iter()
+            .any(|x| x.severity == validation::Severity::Error)
+    }
+
+    /// Writes the config out, once validation has let it through
+    fn save(&mut self) -> iced::Task<Message> {
+        self.toolbar.update(
+            ToolbarMsg::SaveFile(Some(Box::new(self.config.clone()))),
+            &mut self.settings,
+        )
+    }
+
+    /// Runs a toolbar message, once any unsaved changes it would drop
+    /// have been confirmed
+    fn toolbar(&mut self, mut tb: ToolbarMsg) -> iced::Task<Message> {
+        // File operations start here and always finish with one of
+        // the result messages, Error included
+        match tb {
+            ToolbarMsg::OpenFile
+            | ToolbarMsg::OpenRecent(_)
+            | ToolbarMsg::Revert(Some(_))
+            | ToolbarMsg::SaveFile(_)
+            | ToolbarMsg::ExportSelection(_)
+            | ToolbarMsg::ExportScript(_)
+            | ToolbarMsg::ExportPlan(_)
+            | ToolbarMsg::ExportFlowgraph(_)
+            | ToolbarMsg::ExportSigmf(_)
+            | ToolbarMsg::ImportSigmf
+            | ToolbarMsg::ImportCsv(_) => self.in_progress = true,
+            ToolbarMsg::OpenedFile(_)
+            | ToolbarMsg::SuspiciousFile(_)
+            | ToolbarMsg::SavedFile(_)
+            | ToolbarMsg::Exported
+            | ToolbarMsg::ImportedSigmf(_)
+            | ToolbarMsg::ImportedCsv(_)
+            | ToolbarMsg::Cancelled
+            | ToolbarMsg::Error(_) => self.in_progress = false,
+            _ => (),
+        }
+        match &mut tb {
+            ToolbarMsg::OpenedFile((conf, path)) => {
+                self.replace_config(*conf.clone());
+                self.load_notes();
+                self.read_only = self.config.is_newer();
+                self.path = Some(path.clone());
+                self.mark_clean();
+                self.history.clear();
+                self.revalidate();
+            }
+            ToolbarMsg::SuspiciousFile((conf, path, reason)) => {
+                self.pending_open = Some((conf.clone(), path.clone(), reason.clone()));
+            }
+            ToolbarMsg::ConfirmOpen(load) => {
+                if let Some((conf, path, _)) = self.pending_open.take().filter(|_| *load) {
+                    if let Err(e) = self.settings.remember(&path) {
+                        self.notice = Some(format!("Recent files could not be saved: {e}"));
+                    }
+                    self.replace_config(*conf);
+                    self.load_notes();
+                    self.read_only = self.config.is_newer();
+                    self.path = Some(path);
+                    self.mark_clean();
+                    self.history.clear();
+                    self.revalidate();
+                }
+            }
+            ToolbarMsg::SaveFile(_) => {
+                // Saving never works from a result still waiting out the debounce
+                self.revalidate();
+                if !self.validation.is_empty() {
+                    self.confirm_save = true;
+                    self.in_progress = false;
+                    return iced::Task::none();
+                }
+                return self.save();
+            }
+            ToolbarMsg::ConfirmSave(save) => {
+                self.confirm_save = false;
+                if *save && !self.has_errors() {
+                    self.in_progress = true;
+                    return self.save();
+                }
+            }
+            ToolbarMsg::ExportSelection(_) => {
+                let subset = self
+                    .config
+                    .subset(|x| x.enabled && self.config.matches_filter(x));
+                return self.toolbar.update(
+                    ToolbarMsg::ExportSelection(Some(Box::new(subset))),
+                    &mut self.settings,
+                );
+            }
+            ToolbarMsg::ExportScript(None) => {
+                let shell = self.settings.script_shell;
+                let script = self
+                    .config
+                    .subset(|x| self.config.matches_filter(x))
+                    .capture_script(shell, &self.settings.capture_command);
+                return self.toolbar.update(
+                    ToolbarMsg::ExportScript(Some((shell, script))),
+                    &mut self.settings,
+                );
+            }
+            ToolbarMsg::ExportPlan(None) => {
+                let plan = self.config.capture_plan();
+                return self
+                    .toolbar
+                    .update(ToolbarMsg::ExportPlan(Some(plan)), &mut self.settings);
+            }
+            ToolbarMsg::Revert(None) => {
+                self.confirm_revert = self.path.is_some() && self.dirty();
+            }
+            ToolbarMsg::ConfirmRevert(revert) => {
+                self.confirm_revert = false;
+                if let Some(path) = self.path.clone().filter(|_| *revert) {
+                    return self.update(ToolbarMsg::Revert(Some(path)).into());
+                }
+            }
+            ToolbarMsg::NewFile => {
+                self.replace_config(Config::default());
+                self.load_notes();
+                self.read_only = false;
+                self.path = None;
+                self.mark_clean();
+                self.history.clear();
+                self.revalidate();
+            }
+            ToolbarMsg::SavedFile((timestamp, path)) => {
+                self.config.mark_saved(timestamp);
+                self.path = Some(path.clone());
+                self.mark_clean();
+            }
+            ToolbarMsg::Warning(warning) => {
+                self.warning = Some(warning.clone());
+            }
+            ToolbarMsg::ImportedSigmf((name, recording)) => {
+                let mut notice = format!(
+                    "Imported {} frequencies from {name} as a new group, and its sample rate into Capture Settings",
+                    recording.frequencies.len()
+                );
+                if recording.datatype != self.config.capture_settings().datatype() {
+                    notice.push_str(&format!(
+                        "; it was recorded as {}, but this config captures {}",
+                        recording.datatype,
+                        self.config.capture_settings().datatype()
+                    ));
+                }
+                self.notice = Some(notice);
+                return self.edit(Message::ImportSigmf((name.clone(), recording.clone())));
+            }
+            ToolbarMsg::ImportedCsv((group, text)) => {
+                let msg =
+                    frequency::FrequencyMessage::ImportedCsv((group.clone(), std::mem::take(text)));
+                return self.edit(msg.into());
+            }
+            ToolbarMsg::Error(error) => {
+                self.error = Some((error.clone(), std::time::Instant::now() + ERROR_TIMEOUT));
+                return iced::Task::batch([
+                    self.toolbar.update(tb, &mut self.settings),
+                    wake_after(ERROR_TIMEOUT),
+                ]);
+            }
+            _ => (),
+        }
+        self.toolbar.update(tb, &mut self.settings)
+    }
+
+    fn update(&mut self, message: Message) -> iced::Task<Message> {
+        match &message {
+            Message::Frequency(_) => self.frequencies_focused = true,
+            Message::Toolbar(_)
+            | Message::CaptureSettingsMsg(_)
+            | Message::CollectionModes(_)
+            | Message::Scheduling(_)
+            | Message::Device(_)
+            | Message::Preset(_)
+            | Message::Template(_)
+            | Message::Flowgraph(_)
+            | Message::NotesAction(_) => self.frequencies_focused = false,
+            _ => (),
+        }
+        match message {
+            Message::Toolbar(tb) => {
+                // Starting over or opening another file drops unsaved
+                // changes, so ask first
+                if matches!(
+                    tb,
+                    ToolbarMsg::NewFile | ToolbarMsg::OpenFile | ToolbarMsg::OpenRecent(_)
+                ) && self.dirty()
+                {
+                    self.pending_discard = Some(tb);
+                    return iced::Task::none();
+                }
+                if let ToolbarMsg::ConfirmDiscard(discard) = tb {
+                    return match self.pending_discard.take().filter(|_| discard) {
+                        Some(tb) => self.toolbar(tb),
+                        None => iced::Task::none(),
+                    };
+                }
+                self.toolbar(tb)
+            }
+            Message::Shortcut(shortcut) => {
+                let msg = match shortcut {
+                    Shortcut::AddFrequency | Shortcut::AddGroup if !self.frequencies_focused => {
+                        return iced::Task::none();
+                    }
+                    Shortcut::AddFrequency => match self.config.active_group() {
+                        Some(key) => frequency::FrequencyMessage::AddFreq(key.clone()),
+                        None => return iced::Task::none(),
+                    },
+                    Shortcut::AddGroup => frequency::FrequencyMessage::AddGroup,
+                    Shortcut::Undo => return self.update(Message::Undo),
+                    Shortcut::Redo => return self.update(Message::Redo),
+                    Shortcut::FocusNext | Shortcut::FocusPrevious => {
+                        let forward = matches!(shortcut, Shortcut::FocusNext);
+                        return iced::advanced::widget::operate(
+                            iced::advanced::widget::operation::focusable::find_focused(),
+                        )
+                        .collect()
+                        .map(move |ids| Message::FocusStep(forward, ids.into_iter().next()));
+                    }
+                };
+                self.edit(msg.into())
+            }
+            Message::FocusStep(forward, current) => {
+                let chain = self.config.focus_chain(&self.settings.tab_order);
+                match focus::step(&chain, current, forward) {
+                    Some(id) => iced::widget::text_input::focus(id),
+                    None => iced::Task::none(),
+                }
+            }
+            Message::DismissWarning => {
+                self.warning = None;
+                iced::Task::none()
+            }
+            Message::Notice(notice) => {
+                self.notice = Some(notice);
+                iced::Task::none()
+            }
+            Message::DismissNotice => {
+                self.notice = None;
+                iced::Task::none()
+            }
+            Message::Settings(msg) => self.settings.update(msg),
+            Message::Preset(msg) => match msg {
+                capture_settings::PresetMsg::Name(name) => {
+                    self.presets.name = name;
+                    iced::Task::none()
+                }
+                capture_settings::PresetMsg::Select(name) => {
+                    self.presets.selected = Some(name);
+                    iced::Task::none()
+                }
+                capture_settings::PresetMsg::Merge(merge) => {
+                    self.presets.merge = merge;
+                    iced::Task::none()
+                }
+                capture_settings::PresetMsg::Save => {
+                    let name = std::mem::take(&mut self.presets.name).trim().to_string();
+                    let preset = Box::new(self.config.capture_settings().clone());
+                    self.presets.selected = Some(name.clone());
+                    self.settings
+                        .update(settings::SettingsMsg::SavePreset((name, preset)))
+                }
+                capture_settings::PresetMsg::Apply => {
+                    let preset = self
+                        .presets
+                        .selected
+                        .as_ref()
+                        .and_then(|x| self.settings.capture_presets.get(x))
+                        .cloned();
+                    match preset {
+                        Some(preset) => self.edit(
+                            capture_settings::CaptureSettingsMsg::ApplyPreset((
+                                Box::new(preset),
+                                self.presets.merge,
+                            ))
+                            .into(),
+                        ),
+                        None => iced::Task::none(),
+                    }
+                }
+                capture_settings::PresetMsg::Delete => match self.presets.selected.take() {
+                    Some(name) => self
+                        .settings
+                        .update(settings::SettingsMsg::DeletePreset(name)),
+                    None => iced::Task::none(),
+                },
+            },
+            Message::Template(msg) => match msg {
+                templates::TemplateMsg::SaveGroup(key) => {
+                    let Some(freqs) = self.config.group(&key) else {
+                        return iced::Task::none();
+                    };
+                    let template = templates::GroupTemplate::from_group(freqs);
+                    self.templates.selected = Some(key.clone());
+                    self.settings
+                        .update(settings::SettingsMsg::SaveTemplate((key.clone(), template)))
+                        .chain(iced::Task::done(Message::Notice(format!(
+                            "Saved group '{key}' as a template"
+                        ))))
+                }
+                templates::TemplateMsg::Select(name) => {
+                    self.templates.selected = Some(name);
+                    iced::Task::none()
+                }
+                templates::TemplateMsg::Base(base) => {
+                    self.templates.base = base;
+                    iced::Task::none()
+                }
+                templates::TemplateMsg::Name(name) => {
+                    self.templates.name = name;
+                    iced::Task::none()
+                }
+                templates::TemplateMsg::Instantiate => {
+                    let selected = self.templates.selected.as_ref();
+                    let template = selected.and_then(|x| self.settings.group_templates.get(x));
+                    match (selected, template, self.templates.base()) {
+                        (Some(selected), Some(template), Some(base)) => {
+                            let name = match self.templates.name.trim() {
+                                "" => selected.clone(),
+                                name => name.to_string(),
+                            };
+                            let freqs = template.instantiate(base);
+                            self.templates.name.clear();
+                            self.edit(
+                                frequency::FrequencyMessage::InsertGroup((name, freqs)).into(),
+                            )
+                        }
+                        _ => iced::Task::none(),
+                    }
+                }
+                templates::TemplateMsg::Delete => match self.templates.selected.take() {
+                    Some(name) => self
+                        .settings
+                        .update(settings::SettingsMsg::DeleteTemplate(name)),
+                    None => iced::Task::none(),
+                },
+            },
+            Message::Flowgraph(msg) => match msg {
+                grc::FlowgraphMsg::Open => {
+                    self.flowgraph.open = true;
+                    iced::Task::none()
+                }
+                grc::FlowgraphMsg::Frequency(pick) => {
+                    self.flowgraph.frequency = Some(pick);
+                    iced::Task::none()
+                }
+                grc::FlowgraphMsg::Mode(mode) => {
+                    self.flowgraph.mode = Some(mode);
+                    iced::Task::none()
+                }
+                grc::FlowgraphMsg::Export | grc::FlowgraphMsg::ExportSigmf => {
+                    let sigmf = matches!(msg, grc::FlowgraphMsg::ExportSigmf);
+                    let panel = &self.flowgraph;
+                    let export = panel.frequency.as_ref().zip(panel.mode.as_ref()).and_then(
+                        |(pick, mode)| {
+                            if sigmf {
+                                self.config.sigmf(pick, mode).map(ToolbarMsg::ExportSigmf)
+                            } else {
+                                self.config
+                                    .flowgraph(pick, mode)
+                                    .map(ToolbarMsg::ExportFlowgraph)
+                            }
+                        },
+                    );
+                    let msg = match export {
+                        Some(export) => {
+                            self.flowgraph.open = false;
+                            export
+                        }
+                        None => ToolbarMsg::Error(
+                            "The chosen frequency or mode no longer exists".to_string(),
+                        ),
+                    };
+                    self.update(msg.into())
+                }
+                grc::FlowgraphMsg::Close => {
+                    self.flowgraph.open = false;
+                    iced::Task::none()
+                }
+            },
+            Message::TogglePreview => {
+                self.preview_tasks = !self.preview_tasks;
+                iced::Task::none()
+            }
+            Message::ToggleSettings => {
+                self.show_settings = !self.show_settings;
+                iced::Task::none()
+            }
+            Message::HelpTopic(topic) => {
+                self.help_topic = Some(topic);
+                iced::Task::none()
+            }
+            Message::ShowValidation => iced::widget::scrollable::snap_to(
+                config_scroll_id(),
+                iced::widget::scrollable::RelativeOffset::START,
+            ),
+            Message::Undo => {
+                if let Some((previous, generation)) =
+                    self.history.undo((self.config.clone(), self.generation))
+                {
+                    self.config = previous;
+                    self.generation = generation;
+                    self.load_notes();
+                    self.revalidate();
+                }
+                iced::Task::none()
+            }
+            Message::Redo => {
+                if let Some((next, generation)) =
+                    self.history.redo((self.config.clone(), self.generation))
+                {
+                    self.config = next;
+                    self.generation = generation;
+                    self.load_notes();
+                    self.revalidate();
+                }
+                iced::Task::none()
+            }
+            Message::Tick(now) => {
+                if self.validate_after.is_some_and(|due| due <= now) {
+                    self.revalidate();
+                }
+                if self.error.as_ref().is_some_and(|(_, until)| *until <= now) {
+                    self.error = None;
+                }
+                iced::Task::none()
+            }
+            Message::DismissError => {
+                self.error = None;
+                iced::Task::none()
+            }
+            Message::Frequency(frequency::FrequencyMessage::DelGroup(key)) => {
+                let references = self.config.group_references(&key);
+                if references.is_empty() {
+                    self.edit(frequency::FrequencyMessage::DelGroup(key).into())
+                } else {
+                    self.pending_delete = Some((key, references));
+                    iced::Task::none()
+                }
+            }
+            Message::NotesAction(action) => {
+                let is_edit = action.is_edit();
+                self.notes.perform(action);
+                if !is_edit {
+                    return iced::Task::none();
+                }
+                // The editor always ends its text with a newline
+                let text = self.notes.text();
+                let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+                let task = self.edit(Message::Notes(text.clone()));
+                if self.config.notes() != text {
+                    // Refused as read-only
+                    self.load_notes();
+                }
+                task
+            }
+            Message::AllowEditing => {
+                self.read_only = false;
+                iced::Task::none()
+            }
+            Message::ConfirmDeleteGroup(answer) => match (self.pending_delete.take(), answer) {
+                (Some((key, _)), Some(clear)) => self.edit(Message::DeleteGroup((key, clear))),
+                _ => iced::Task::none(),
+            },
+            Message::None => iced::Task::none(),
+
+            _ if history::view_only(&message) => self.config.update(message),
+            _ => self.edit(message),
+        }
+    }
+
+    /// File, unsaved changes, validation counts, last save time and the
+    /// last failed file operation, which a click dismisses
+    fn status_view(&self) -> iced::Element<'_, Message> {
+        let issues = &self.validation;
+        let errors = issues
+            .iter()
+            .filter(|x| x.severity == validation::Severity::Error)
+            .count();
+        iced::widget::row![
+            iced::widget::text(
+                self.path
+                    .as_ref()
+                    .map(|x| x.display().to_string())
+                    .unwrap_or("Untitled".to_string())
+            ),
+            iced::widget::text(if self.dirty() {
+                "Unsaved changes"
+            } else {
+                "No changes"
+            }),
+            iced::widget::button(iced::widget::text(format!(
+                "{errors} errors, {} warnings",
+                issues.len() - errors
+            )))
+            .style(iced::widget::button::text)
+            .padding(0)
+            .on_press(Message::ShowValidation),
+            iced::widget::text(format!(
+                "Last saved: {}",
+                self.config.modified().unwrap_or("never")
+            )),
+        ]
+        .push_maybe(self.in_progress.then(|| iced::widget::text("Working...")))
+        .push_maybe(self.error.as_ref().map(|(error, _)| {
+            iced::widget::button(iced::widget::text(error).style(iced::widget::text::danger))
+                .style(iced::widget::button::text)
+                .padding(0)
+                .on_press(Message::DismissError)
+        }))
+        .spacing(30)
+        .into()
+    }
+
+    fn view(&self) -> iced::Element<'_, Message> {
+        let warning = self.warning.as_ref().map(|warning| {
+            iced::widget::row![
+                iced::widget::text(warning).style(iced::widget::text::danger),
+                iced::widget::button("Dismiss").on_press(Message::DismissWarning),
+            ]
+            .spacing(10)
+        });
+        let notice = self.notice.as_ref().map(|notice| {
+            iced::widget::row![
+                iced::widget::text(notice),
+                iced::widget::button("Dismiss").on_press(Message::DismissNotice),
+            ]
+            .spacing(10)
+        });
+        let pending_open = self.pending_open.as_ref().map(|(_, _, reason)| {
+            iced::widget::row![
+                iced::widget::text(reason).style(validation::warning),
+                iced::widget::button("Load Anyway").on_press(ToolbarMsg::ConfirmOpen(true).into()),
+                iced::widget::button("Cancel").on_press(ToolbarMsg::ConfirmOpen(false).into()),
+            ]
+            .spacing(10)
+        });
+        let confirm_revert = self.confirm_revert.then(|| {
+            iced::widget::row![
+                iced::widget::text(format!(
+                    "Discard all unsaved changes and reload {}?",
+                    self.path
+                        .as_ref()
+                        .map(|x| x.display().to_string())
+                        .unwrap_or_default()
+                ))
+                .style(validation::warning),
+                iced::widget::button("Revert").on_press(ToolbarMsg::ConfirmRevert(true).into()),
+                iced::widget::button("Cancel").on_press(ToolbarMsg::ConfirmRevert(false).into()),
+            ]
+            .spacing(10)
+        });
+        let pending_discard = self.pending_discard.as_ref().map(|_| {
+            iced::widget::row![
+                iced::widget::text("There are unsaved changes. Discard them?")
+                    .style(validation::warning),
+                iced::widget::button("Discard").on_press(ToolbarMsg::ConfirmDiscard(true).into()),
+                iced::widget::button("Cancel").on_press(ToolbarMsg::ConfirmDiscard(false).into()),
+            ]
+            .spacing(10)
+        });
+        // Errors block the save; warnings only need confirming
+        let confirm_save = self.confirm_save.then(|| {
+            let errors = self.has_errors();
+            let issues: Vec<validation::ValidationIssue> = self
+                .validation
+                .iter()
+                .filter(|x| !errors || x.severity == validation::Severity::Error)
+                .cloned()
+                .collect();
+            iced::widget::column![
+                if errors {
+                    iced::widget::text("The config can't be saved until these errors are fixed:")
+                        .style(iced::widget::text::danger)
+                } else {
+                    iced::widget::text("The config has warnings. Save anyway?")
+                        .style(validation::warning)
+                },
+                validation::view(&issues),
+                iced::widget::row![
+                    iced::widget::button("Save Anyway")
+                        .on_press_maybe((!errors).then_some(ToolbarMsg::ConfirmSave(true).into())),
+                    iced::widget::button(if errors { "Close" } else { "Cancel" })
+                        .on_press(ToolbarMsg::ConfirmSave(false).into()),
+                ]
+                .spacing(10),
+            ]
+            .spacing(4)
+        });
+        let read_only = self.read_only.then(|| {
+            iced::widget::row![
+                iced::widget::text(format!(
+                    "This file uses format version {}, newer than the {} this version understands. It is \
+                     open read-only; fields this version doesn't know are kept when saving.",
+                    self.config.version(),
+                    config::FORMAT_VERSION,
+                ))
+                .style(validation::warning),
+                iced::widget::button("Edit Anyway").on_press(Message::AllowEditing),
+            ]
+            .spacing(10)
+        });
+        let pending_delete = self.pending_delete.as_ref().map(|(key, references)| {
+            iced::widget::column![
+                iced::widget::text(format!(
+                    "Group '{key}' is used by {}. Deleting it leaves them pointing at nothing.",
+                    references.join(", ")
+                ))
+                .style(validation::warning),
+                iced::widget::row![
+                    iced::widget::button("Delete and Clear References")
+                        .on_press(Message::ConfirmDeleteGroup(Some(true))),
+                    iced::widget::button("Delete Anyway")
+                        .on_press(Message::ConfirmDeleteGroup(Some(false))),
+                    iced::widget::button("Cancel").on_press(Message::ConfirmDeleteGroup(None)),
+                ]
+                .spacing(10),
+            ]
+            .spacing(4)
+        });
+        iced::widget::column![
+            iced::widget::container(
+                self.toolbar
+                    .view(
+                        self.settings.show_help,
+                        self.path.is_some() && self.dirty(),
+                        &self.settings.recent_files
+                    )
+                    .push(
+                        iced::widget::button("Undo")
+                            .on_press_maybe(self.history.can_undo().then_some(Message::Undo)),
+                    )
+                    .push(
+                        iced::widget::button("Redo")
+                            .on_press_maybe(self.history.can_redo().then_some(Message::Redo)),
+                    )
+                    .push(
+                        iced::widget::button(if self.preview_tasks {
+                            "Edit Config"
+                        } else {
+                            "Preview Tasks"
+                        })
+                        .on_press(Message::TogglePreview),
+                    ),
+            )
+            .align_top(iced::Length::Shrink)
+            .align_left(iced::Length::Shrink),
+        ]
+        .push_maybe(self.show_settings.then(|| self.settings.view()))
+        .push_maybe(pending_open)
+        .push_maybe(read_only)
+        .push_maybe(confirm_revert)
+        .push_maybe(pending_discard)
+        .push_maybe(confirm_save)
+        .push_maybe(pending_delete)
+        .push_maybe(self.flowgraph.open.then(|| {
+            self.flowgraph
+                .view(self.config.frequency_picks(), self.config.mode_names())
+        }))
+        .push_maybe(warning)
+        .push_maybe(notice)
+        .push(
+            iced::widget::row![
+                iced::widget::Scrollable::new(if self.preview_tasks {
+                    self.config.tasks_view()
+                } else {
+                    self.config.view(
+                        self.presets
+                            .view(self.settings.capture_presets.keys().cloned().collect()),
+                        self.templates
+                            .view(self.settings.group_templates.keys().cloned().collect()),
+                        iced::widget::text_editor(&self.notes)
+                            .placeholder("What this config is for, why these frequencies...")
+                            .on_action(Message::NotesAction)
+                            .height(120)
+                            .into(),
+                        &self.validation,
+                    )
+                })
+                .id(config_scroll_id())
+            ]
+            .push_maybe(self.settings.show_help.then(|| help::view(self.help_topic)))
+            .spacing(20)
+            .height(iced::Length::Fill),
+        )
+        .push(self.status_view())
+        .spacing(30)
+        .into()
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        iced::keyboard::on_key_press(|key, modifiers| {
+            if let iced::keyboard::Key::Named(iced::keyboard::key::Named::Tab) = key {
+                return Some(Message::Shortcut(if modifiers.shift() {
+                    Shortcut::FocusPrevious
+                } else {
+                    Shortcut::FocusNext
+                }));
+            }
+            if !modifiers.command() {
+                return None;
+            }
+            match key.as_ref() {
+                iced::keyboard::Key::Character("f") => Some(Shortcut::AddFrequency),
+                iced::keyboard::Key::Character("g") => Some(Shortcut::AddGroup),
+                iced::keyboard::Key::Character("z" | "Z") if modifiers.shift() => {
+                    Some(Shortcut::Redo)
+                }
+                iced::keyboard::Key::Character("z") => Some(Shortcut::Undo),
+                iced::keyboard::Key::Character("y") => Some(Shortcut::Redo),
+                _ => None,
+            }
+            .map(Message::Shortcut)
+        })
+    }
+}
+
+/// Sends a single `Message::Tick` once `delay` has passed. Sleeps on a
+/// thread of its own, as the app runs without an async timer, rather than
+/// redrawing every frame until then.
+fn wake_after(delay: std::time::Duration) -> iced::Task<Message> {
+    let (sender, receiver) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let _ = sender.send(std::time::Instant::now());
+    });
+    iced::Task::perform(receiver, |now| {
+        Message::Tick(now.unwrap_or_else(|_| std::time::Instant::now()))
+    })
+}
+
+/// Opens the editor window
+pub fn run() -> iced::Result {
+    iced::application(App::title, App::update, App::view)
+        .subscription(App::subscription)
+        .theme(|app| app.settings.theme())
+        .scale_factor(|app| app.settings.scale)
+        .run_with(|| (App::new(), iced::Task::none()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_shortcuts_only_follow_frequency_input() {
+        let mut app = App::default();
+        let _ = app.update(Message::Shortcut(Shortcut::AddGroup));
+        assert_eq!(app.config.active_group(), None);
+
+        let _ = app.update(frequency::FrequencyMessage::AddGroup.into());
+        let key = app.config.active_group().cloned().unwrap();
+        let _ = app.update(Message::Shortcut(Shortcut::AddFrequency));
+        assert_eq!(app.config.group(&key).unwrap().len(), 1);
+
+        let _ = app.update(Message::Scheduling(scheduling::SchedulingMsg::Add));
+        let _ = app.update(Message::Shortcut(Shortcut::AddFrequency));
+        let _ = app.update(Message::Shortcut(Shortcut::AddGroup));
+        assert_eq!(app.config.group(&key).unwrap().len(), 1);
+        assert_eq!(app.config.active_group(), Some(&key));
+    }
+}
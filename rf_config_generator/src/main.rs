@@ -1,69 +1,31 @@
-use std::{fs::File, io::BufWriter};
-
-use crate::{config::Config, toolbar::ToolbarMsg};
-
-mod capture_settings;
-mod collection_modes;
-mod config;
-mod frequency;
-mod scheduling;
-mod toolbar;
-mod utils;
-
-#[derive(Debug, Clone)]
-enum Message {
-    None,
-    Toolbar(ToolbarMsg),
-    Frequency(frequency::FrequencyMessage),
-    CaptureSettingsMsg(capture_settings::CaptureSettingsMsg),
-    CollectionModes(collection_modes::CollectionModesMsg),
-    Scheduling(scheduling::SchedulingMsg),
-}
-
-#[derive(Default)]
-struct App {
-    toolbar: toolbar::Toolbar,
-    config: config::Config,
-}
-
-impl App {
-    fn update(&mut self, message: Message) -> iced::Task<Message> {
-        match message {
-            Message::Toolbar(mut tb) => {
-                match &mut tb {
-                    ToolbarMsg::OpenedFile(conf) => {
-                        self.config = conf.clone();
-                    }
-                    ToolbarMsg::SaveFile(_) => {
-                        return self
-                            .toolbar
-                            .update(ToolbarMsg::SaveFile(Some(self.config.clone())));
-                    }
-                    ToolbarMsg::NewFile => {
-                        self.config = Config::default();
-                    }
-                    _ => (),
-                }
-                self.toolbar.update(tb)
+use std::{path::Path, process::ExitCode};
+
+use rf_config_generator::cli;
+
+const USAGE: &str = "\
+usage: rf_config_generator                      open the editor
+       rf_config_generator --validate <file|->  print validation issues as JSON
+       rf_config_generator --expand-tasks       config on stdin to capture tasks as JSON
+       rf_config_generator --to-yaml            config on stdin to YAML
+       rf_config_generator --print-schema       JSON schema of the config file";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => match rf_config_generator::run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
             }
-            Message::None => iced::Task::none(),
-
-            _ => self.config.update(message).into(),
+        },
+        [flag, path] if flag == "--validate" => cli::validate(Path::new(path)),
+        [flag] if flag == "--expand-tasks" => cli::filter(cli::Transform::ExpandTasks),
+        [flag] if flag == "--to-yaml" => cli::filter(cli::Transform::ToYaml),
+        [flag] if flag == "--print-schema" => cli::print_schema(),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
         }
     }
-
-    fn view(&self) -> iced::Element<Message> {
-        iced::widget::column![
-            iced::widget::container(self.toolbar.view())
-                .align_top(iced::Length::Shrink)
-                .align_left(iced::Length::Shrink),
-            iced::widget::Scrollable::new(self.config.view()),
-        ]
-        .spacing(30)
-        .into()
-    }
-}
-
-fn main() -> iced::Result {
-    iced::run("Hello World!", App::update, App::view)
 }
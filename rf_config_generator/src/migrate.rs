@@ -0,0 +1,127 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::config::{Config, FORMAT_VERSION};
+
+/// Upgrades to older files, the one at index n taking a file from format
+/// version n to n + 1
+const STEPS: &[fn(&mut Map<String, Value>)] = &[numbers_from_strings];
+
+/// Format version a raw config file was written in, 0 when unrecorded
+fn version(raw: &Map<String, Value>) -> u64 {
+    raw.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Reads a raw config file, upgrading older formats first. Also returns
+/// the upgraded file, for checks that look at what it contains. Files from
+/// a newer format are read as they are and fail with a clear message if
+/// they no longer fit.
+pub fn read(mut raw: Value) -> Result<(Config, Value), String> {
+    if let Some(map) = raw.as_object_mut() {
+        let from = version(map);
+        for step in STEPS.iter().skip(from as usize) {
+            step(map);
+        }
+        if from < u64::from(FORMAT_VERSION) {
+            map.insert("version".to_string(), FORMAT_VERSION.into());
+        }
+    }
+    match Config::deserialize(&raw) {
+        Ok(config) => Ok((config, raw)),
+        Err(e) => match raw.as_object().map(version) {
+            Some(v) if v > u64::from(FORMAT_VERSION) => Err(format!(
+                "it was written by a newer version of this tool (format {v}, this build reads up to {FORMAT_VERSION})"
+            )),
+            _ => Err(e.to_string()),
+        },
+    }
+}
+
+/// 0 to 1: the original Python editor saved values typed into a blank
+/// entry as strings, e.g. "freq": "2.4e9". Numeric strings in number
+/// fields become numbers; anything else is left for the reader to reject.
+fn numbers_from_strings(raw: &mut Map<String, Value>) {
+    fn number(value: &mut Value) {
+        let parsed = value.as_str().and_then(|x| x.trim().parse::<f64>().ok());
+        if let Some(number) = parsed.and_then(serde_json::Number::from_f64) {
+            *value = Value::Number(number);
+        }
+    }
+    let groups = raw.get_mut("frequencies").and_then(Value::as_object_mut);
+    for freq in groups
+        .into_iter()
+        .flat_map(|x| x.values_mut())
+        .filter_map(Value::as_array_mut)
+        .flatten()
+    {
+        freq.get_mut("freq").map(number);
+    }
+    if let Some(settings) = raw
+        .get_mut("capture_settings")
+        .and_then(Value::as_object_mut)
+    {
+        for key in ["sample_rates", "durations", "gains"] {
+            settings
+                .get_mut(key)
+                .and_then(Value::as_array_mut)
+                .into_iter()
+                .flatten()
+                .for_each(number);
+        }
+    }
+    let modes = raw
+        .get_mut("collection_modes")
+        .and_then(Value::as_object_mut);
+    for mode in modes.into_iter().flat_map(|x| x.values_mut()) {
+        for key in ["sample_rate", "duration", "gain"] {
+            mode.get_mut(key).map(number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v0_strings_become_numbers_and_unknown_fields_stay() {
+        let v0 = serde_json::json!({
+            "frequencies": {
+                "wifi": [{"name": "ch1", "freq": "2.412e9", "description": "", "antenna_note": "roof"}],
+            },
+            "capture_settings": {
+                "sample_rates": ["1e6", 2e6], "durations": ["60"], "gains": [" 30 "],
+                "compression": "zstd", "compression_level": 3, "legacy_flag": true,
+            },
+            "collection_modes": {
+                "survey": {"sample_rate": "1e6", "duration": "60", "gain": "30", "bias_tee": false},
+            },
+            "scheduling": {"time_slots": [{"name": "am", "start": "06:00", "end": "09:00", "owner": "ops"}]},
+            "site": {"lat": 51.5, "lon": -0.1},
+        });
+        let (config, _) = read(v0).unwrap();
+        let written = serde_json::to_value(&config).unwrap();
+        // Defaults the file left out are written out; nothing is lost
+        let expected = serde_json::json!({
+            "version": 1,
+            "frequencies": {
+                "wifi": [{"name": "ch1", "freq": 2.412e9, "description": "", "enabled": true, "antenna_note": "roof"}],
+            },
+            "capture_settings": {
+                "sample_rates": [1e6, 2e6], "durations": [60.0], "gains": [30.0],
+                "compression": "zstd", "compression_level": 3, "datatype": "cf32", "legacy_flag": true,
+            },
+            "collection_modes": {
+                "survey": {"sample_rate": 1e6, "duration": 60.0, "gain": 30.0, "bias_tee": false},
+            },
+            "scheduling": {
+                "time_slots": [{"name": "am", "start": "06:00", "end": "09:00", "enabled": true, "owner": "ops"}],
+            },
+            "site": {"lat": 51.5, "lon": -0.1},
+        });
+        assert_eq!(written, expected);
+        // Already current, so a second read changes nothing
+        let (again, _) = read(written.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), written);
+    }
+}
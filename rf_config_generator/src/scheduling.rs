@@ -1,11 +1,16 @@
-use crate::Message;
-
+use crate::{
+    Message,
+    focus::{self, Field},
+    validation::{self, ValidationIssue},
+};
 
 #[derive(Debug, Clone)]
 pub enum SchedulingMsg {
-   Change(usize, ChangeMsg),
-   Add,
-   Delete(usize),
+    // Slots are addressed by id, not position, so a message still in flight
+    // when another slot is deleted can't land on the wrong slot
+    Change(u64, ChangeMsg),
+    Add,
+    Delete(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -13,76 +18,936 @@ pub enum ChangeMsg {
     Name(String),
     Start(String),
     End(String),
+    CrossesMidnight(bool),
+    Enabled(bool),
+    Group(Option<String>),
+    Mode(Option<String>),
+    Repeat(Repeat),
+    // Adds or removes a day of a weekly slot
+    ToggleDay(chrono::Weekday),
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct Scheduling {
     time_slots: Vec<TimeSlot>,
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Scheduling {
+    pub fn is_empty(&self) -> bool {
+        self.time_slots.is_empty()
+    }
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 struct TimeSlot {
+    #[serde(skip, default = "rand::random")]
+    id: u64,
     name: String,
     start: String,
     end: String,
+    // The user confirmed that an end time-of-day before the start is
+    // meant to run past midnight
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    crosses_midnight: bool,
+    // Disabled slots are kept but skipped, like disabled frequencies
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    // Frequency group captured during the slot, by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    // Collection mode captured with during the slot, by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Recurrence::is_daily")]
+    recurrence: Recurrence,
+    // Fields written by a newer version, kept so saving doesn't drop them
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for TimeSlot {
+    fn default() -> Self {
+        Self {
+            id: rand::random(),
+            name: String::new(),
+            start: String::new(),
+            end: String::new(),
+            crosses_midnight: false,
+            enabled: true,
+            group: None,
+            mode: None,
+            recurrence: Recurrence::Daily,
+            unknown: Default::default(),
+        }
+    }
+}
+
+/// A slot's start or end: a time of day repeating daily, or one moment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotTime {
+    Daily(chrono::NaiveTime),
+    // As written; an ISO-8601 offset is checked but not applied
+    At(chrono::NaiveDateTime),
+}
+
+/// Parses "HH:MM" or "HH:MM:SS"
+fn parse_time_of_day(s: &str) -> Option<chrono::NaiveTime> {
+    let s = s.trim();
+    chrono::NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .ok()
+}
+
+/// Parses a time of day or an ISO-8601 date and time, e.g.
+/// "2024-05-01T08:00" or "2024-05-01T08:00:00+02:00". Ok(None) is an
+/// empty field, which is unset rather than wrong.
+pub fn parse_slot_time(s: &str) -> Result<Option<SlotTime>, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    if let Some(time) = parse_time_of_day(s) {
+        return Ok(Some(SlotTime::Daily(time)));
+    }
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(s).map(|x| x.naive_local()))
+        .map(|x| Some(SlotTime::At(x)))
+        .map_err(|_| format!("'{s}' is neither HH:MM nor an ISO-8601 date and time"))
+}
+
+const DAY: u32 = 86_400;
+const WEEK: u32 = 7 * DAY;
+
+/// How often a slot's window comes round
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    // Start and end are dates and times
+    Once,
+    // Start and end are times of day, as in files from before recurrence
+    #[default]
+    Daily,
+    Weekly {
+        days: Vec<chrono::Weekday>,
+    },
+}
+
+impl Recurrence {
+    fn is_daily(&self) -> bool {
+        *self == Recurrence::Daily
+    }
+
+    fn repeat(&self) -> Repeat {
+        match self {
+            Recurrence::Once => Repeat::Once,
+            Recurrence::Daily => Repeat::Daily,
+            Recurrence::Weekly { .. } => Repeat::Weekly,
+        }
+    }
+
+    /// Days of the week the window opens on, None for a one-off
+    fn days(&self) -> Option<Vec<chrono::Weekday>> {
+        match self {
+            Recurrence::Once => None,
+            Recurrence::Daily => Some(WEEKDAYS.to_vec()),
+            Recurrence::Weekly { days } => Some(days.clone()),
+        }
+    }
+}
+
+/// The kinds of recurrence, as the slot's pick list offers them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Repeat {
+    Once,
+    Daily,
+    Weekly,
+}
+
+impl std::fmt::Display for Repeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Once => write!(f, "once"),
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly"),
+        }
+    }
 }
 
+const WEEKDAYS: [chrono::Weekday; 7] = [
+    chrono::Weekday::Mon,
+    chrono::Weekday::Tue,
+    chrono::Weekday::Wed,
+    chrono::Weekday::Thu,
+    chrono::Weekday::Fri,
+    chrono::Weekday::Sat,
+    chrono::Weekday::Sun,
+];
+
+/// Splits `from..to` (seconds into the week, `to` at most a week past
+/// `from`) where it runs past the end of the week
+fn wrap_week(from: u32, to: u32) -> Vec<(u32, u32)> {
+    if to <= WEEK {
+        vec![(from, to)]
+    } else {
+        vec![(from, WEEK), (0, to - WEEK)]
+    }
+}
+
+/// Seconds into the week (from Monday 00:00) a repeating window covers on
+/// each of `days`. A window whose end is before its start runs into the
+/// next day; one whose start and end are equal covers nothing.
+fn week_spans(
+    days: &[chrono::Weekday],
+    start: chrono::NaiveTime,
+    end: chrono::NaiveTime,
+) -> Vec<(u32, u32)> {
+    use chrono::Timelike;
+    let (start, end) = (
+        start.num_seconds_from_midnight(),
+        end.num_seconds_from_midnight(),
+    );
+    if start == end {
+        return Vec::new();
+    }
+    let length = if end > start {
+        end - start
+    } else {
+        end + DAY - start
+    };
+    days.iter()
+        .flat_map(|day| {
+            let from = day.num_days_from_monday() * DAY + start;
+            wrap_week(from, from + length)
+        })
+        .collect()
+}
+
+/// Half-open intervals overlap only when each starts before the other
+/// ends, so a slot ending as the next begins doesn't count
+fn intersects<T: PartialOrd>(a: (T, T), b: (T, T)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+/// Whether a one-off window meets any occurrence of a repeating one
+fn dated_meets_repeating(
+    (start, end): (chrono::NaiveDateTime, chrono::NaiveDateTime),
+    repeating: &[(u32, u32)],
+) -> bool {
+    use chrono::{Datelike, Timelike};
+    let length = (end - start).num_seconds();
+    if length >= i64::from(WEEK) {
+        return !repeating.is_empty();
+    }
+    let from = start.weekday().num_days_from_monday() * DAY + start.num_seconds_from_midnight();
+    wrap_week(from, from + length as u32)
+        .into_iter()
+        .any(|x| repeating.iter().any(|y| intersects(x, *y)))
+}
+
+/// When a slot runs, once its times parse
+#[derive(Debug, Clone)]
+enum Window {
+    // Seconds into the week, every week
+    Repeating(Vec<(u32, u32)>),
+    At((chrono::NaiveDateTime, chrono::NaiveDateTime)),
+}
 
+impl Window {
+    fn overlaps(&self, other: &Window) -> bool {
+        match (self, other) {
+            (Window::Repeating(a), Window::Repeating(b)) => {
+                a.iter().any(|x| b.iter().any(|y| intersects(*x, *y)))
+            }
+            (Window::At(a), Window::At(b)) => intersects(*a, *b),
+            (Window::At(at), Window::Repeating(repeating))
+            | (Window::Repeating(repeating), Window::At(at)) => {
+                dated_meets_repeating(*at, repeating)
+            }
+        }
+    }
+}
 
 impl TimeSlot {
+    /// When the slot runs, None while its times are unset or wrong
+    fn window(&self) -> Option<Window> {
+        match (
+            parse_slot_time(&self.start),
+            parse_slot_time(&self.end),
+            self.recurrence.days(),
+        ) {
+            (Ok(Some(SlotTime::Daily(start))), Ok(Some(SlotTime::Daily(end))), Some(days)) => {
+                Some(Window::Repeating(week_spans(&days, start, end)))
+            }
+            (Ok(Some(SlotTime::At(start))), Ok(Some(SlotTime::At(end))), None) if start < end => {
+                Some(Window::At((start, end)))
+            }
+            _ => None,
+        }
+    }
+
+    /// True when start and end are both times of day and the window runs
+    /// past midnight, e.g. 23:00 to 01:00
+    pub fn wraps_midnight(&self) -> bool {
+        match (parse_slot_time(&self.start), parse_slot_time(&self.end)) {
+            (Ok(Some(SlotTime::Daily(start))), Ok(Some(SlotTime::Daily(end)))) => end < start,
+            _ => false,
+        }
+    }
+
+    /// What is wrong with the start and end, if anything. Unset fields
+    /// aren't errors.
+    pub fn time_errors(&self) -> Vec<String> {
+        let (start, end) = (parse_slot_time(&self.start), parse_slot_time(&self.end));
+        let mut errors: Vec<String> = [("start", &start), ("end", &end)]
+            .into_iter()
+            .filter_map(|(label, time)| time.as_ref().err().map(|e| format!("{label} {e}")))
+            .collect();
+        match (start, end) {
+            (Ok(Some(SlotTime::At(start))), Ok(Some(SlotTime::At(end)))) if end <= start => {
+                errors.push("end must be after start".to_string());
+            }
+            (Ok(Some(SlotTime::Daily(_))), Ok(Some(SlotTime::At(_))))
+            | (Ok(Some(SlotTime::At(_))), Ok(Some(SlotTime::Daily(_)))) => {
+                errors.push("start and end must both be times of day or both dates".to_string());
+            }
+            (start, end) => {
+                let times = [start, end].into_iter().filter_map(|x| x.ok().flatten());
+                let dated = times.clone().any(|x| matches!(x, SlotTime::At(_)));
+                let daily = times.clone().any(|x| matches!(x, SlotTime::Daily(_)));
+                if self.recurrence == Recurrence::Once && daily {
+                    errors.push(
+                        "a one-off slot takes dates and times, e.g. 2024-05-01T08:00".to_string(),
+                    );
+                } else if self.recurrence != Recurrence::Once && dated {
+                    errors.push("a repeating slot takes times of day (HH:MM)".to_string());
+                }
+            }
+        }
+        if self.recurrence == (Recurrence::Weekly { days: Vec::new() }) {
+            errors.push("a weekly slot needs at least one day".to_string());
+        }
+        errors
+    }
+
     pub fn update(&mut self, msg: ChangeMsg) {
         match msg {
             ChangeMsg::Name(c) => self.name = c,
             ChangeMsg::Start(c) => self.start = c,
             ChangeMsg::End(c) => self.end = c,
+            ChangeMsg::CrossesMidnight(c) => self.crosses_midnight = c,
+            ChangeMsg::Enabled(c) => self.enabled = c,
+            ChangeMsg::Group(c) => self.group = c,
+            ChangeMsg::Mode(c) => self.mode = c,
+            ChangeMsg::Repeat(Repeat::Once) => self.recurrence = Recurrence::Once,
+            ChangeMsg::Repeat(Repeat::Daily) => self.recurrence = Recurrence::Daily,
+            ChangeMsg::Repeat(Repeat::Weekly) => {
+                // Every day to begin with, which runs as daily did
+                if !matches!(self.recurrence, Recurrence::Weekly { .. }) {
+                    self.recurrence = Recurrence::Weekly {
+                        days: WEEKDAYS.to_vec(),
+                    };
+                }
+            }
+            ChangeMsg::ToggleDay(day) => {
+                if let Recurrence::Weekly { days } = &mut self.recurrence {
+                    match days.iter().position(|x| *x == day) {
+                        Some(i) => {
+                            days.remove(i);
+                        }
+                        None => {
+                            days.push(day);
+                            days.sort_by_key(|x| x.num_days_from_monday());
+                        }
+                    }
+                }
+            }
         }
     }
 
-    pub fn view(&self) -> iced::widget::Column<'_, ChangeMsg> {
+    /// The group and mode named by the slot that aren't among `groups`
+    /// and `modes`, e.g. deleted since the slot was set up
+    fn missing(&self, groups: &[String], modes: &[String]) -> Vec<(&'static str, &str)> {
+        [("group", &self.group, groups), ("mode", &self.mode, modes)]
+            .into_iter()
+            .filter_map(|(label, name, names)| {
+                name.as_ref()
+                    .filter(|x| !names.contains(x))
+                    .map(|x| (label, x.as_str()))
+            })
+            .collect()
+    }
+
+    /// Fields of a disabled slot are greyed out and read-only until it is
+    /// enabled again. `groups` and `modes` are the frequency groups and
+    /// collection modes it can capture, `overlapping` the slots it
+    /// collides with.
+    pub fn view(
+        &self,
+        groups: Vec<String>,
+        modes: Vec<String>,
+        overlapping: Vec<String>,
+    ) -> iced::widget::Column<'_, ChangeMsg> {
+        let enabled = self.enabled;
+        let missing = self.missing(&groups, &modes);
+        // Pick lists can't be made read-only, so a disabled slot shows
+        // its choices as text
+        let pick = |names: Vec<String>,
+                    picked: &Option<String>,
+                    on_pick: fn(Option<String>) -> ChangeMsg| {
+            if enabled {
+                iced::widget::pick_list(names, picked.clone(), move |c| on_pick(Some(c)))
+                    .placeholder("any")
+                    .into()
+            } else {
+                iced::Element::from(iced::widget::text(
+                    picked.clone().unwrap_or("any".to_string()),
+                ))
+            }
+        };
         iced::widget::column![
+            iced::widget::checkbox("enabled", self.enabled).on_toggle(ChangeMsg::Enabled),
             iced::widget::row![
                 iced::widget::text("name"),
-                iced::widget::text_input("", &self.name).on_input(|c| ChangeMsg::Name(c)),
+                iced::widget::text_input("", &self.name)
+                    .id(focus::id(Field::SlotName, self.id))
+                    .on_input_maybe(enabled.then_some(ChangeMsg::Name)),
             ],
             iced::widget::row![
                 iced::widget::text("start"),
-                iced::widget::text_input("", &self.start).on_input(|c| ChangeMsg::Start(c)),
+                iced::widget::text_input("HH:MM or date", &self.start)
+                    .id(focus::id(Field::SlotStart, self.id))
+                    .style(time_style(&self.start))
+                    .on_input_maybe(enabled.then_some(ChangeMsg::Start)),
             ],
             iced::widget::row![
                 iced::widget::text("end"),
-                iced::widget::text_input("", &self.end).on_input(|c| ChangeMsg::End(c)),
+                iced::widget::text_input("HH:MM or date", &self.end)
+                    .id(focus::id(Field::SlotEnd, self.id))
+                    .style(time_style(&self.end))
+                    .on_input_maybe(enabled.then_some(ChangeMsg::End)),
+            ],
+            iced::widget::row![
+                iced::widget::text("repeats"),
+                if enabled {
+                    iced::widget::pick_list(
+                        [Repeat::Once, Repeat::Daily, Repeat::Weekly],
+                        Some(self.recurrence.repeat()),
+                        ChangeMsg::Repeat,
+                    )
+                    .into()
+                } else {
+                    iced::Element::from(iced::widget::text(self.recurrence.repeat().to_string()))
+                },
             ],
         ]
+        .push_maybe(match &self.recurrence {
+            Recurrence::Weekly { days } => Some(iced::widget::row(WEEKDAYS.iter().map(|day| {
+                iced::widget::button(iced::widget::text(day.to_string()))
+                    .style(if days.contains(day) {
+                        iced::widget::button::primary
+                    } else {
+                        iced::widget::button::secondary
+                    })
+                    .on_press_maybe(enabled.then_some(ChangeMsg::ToggleDay(*day)))
+                    .into()
+            }))),
+            _ => None,
+        })
+        .push(iced::widget::row![
+            iced::widget::text("group"),
+            pick(groups, &self.group, ChangeMsg::Group),
+            iced::widget::button("Clear").on_press_maybe(
+                (enabled && self.group.is_some()).then_some(ChangeMsg::Group(None))
+            ),
+        ])
+        .push(iced::widget::row![
+            iced::widget::text("mode"),
+            pick(modes, &self.mode, ChangeMsg::Mode),
+            iced::widget::button("Clear")
+                .on_press_maybe((enabled && self.mode.is_some()).then_some(ChangeMsg::Mode(None))),
+        ])
+        .extend(missing.into_iter().map(|(label, name)| {
+            iced::widget::text(format!("{label} '{name}' no longer exists"))
+                .style(validation::warning)
+                .into()
+        }))
+        .extend(overlapping.into_iter().map(|other| {
+            iced::widget::text(format!("overlaps with {other}"))
+                .style(validation::warning)
+                .into()
+        }))
+        .push_maybe(self.wraps_midnight().then(|| {
+            iced::widget::column![
+                iced::widget::text("crosses midnight").style(validation::warning),
+                iced::widget::checkbox("intended", self.crosses_midnight)
+                    .on_toggle_maybe(enabled.then_some(ChangeMsg::CrossesMidnight)),
+            ]
+        }))
+    }
+}
+
+fn time_style(
+    text: &str,
+) -> fn(&iced::Theme, iced::widget::text_input::Status) -> iced::widget::text_input::Style {
+    match parse_slot_time(text) {
+        Ok(_) => iced::widget::text_input::default,
+        Err(_) => validation::invalid_input,
     }
 }
 
 impl Scheduling {
+    // How messages refer to the slot at `i`
+    fn label(&self, i: usize) -> String {
+        match self.time_slots[i].name.as_str() {
+            "" => format!("schedule slot {}", i + 1),
+            name => format!("schedule slot '{name}'"),
+        }
+    }
+
+    /// Names of the slots capturing `group`, for telling the user what
+    /// deleting it would affect
+    pub fn group_references(&self, group: &str) -> Vec<String> {
+        (0..self.time_slots.len())
+            .filter(|i| self.time_slots[*i].group.as_deref() == Some(group))
+            .map(|i| self.label(i))
+            .collect()
+    }
+
+    /// Pairs of enabled slots, by position, whose windows share some time
+    /// and so would both want the SDR. Slots with unset or bad times are
+    /// left out.
+    pub fn overlaps(&self) -> Vec<(usize, usize)> {
+        let windows: Vec<(usize, Window)> = self
+            .time_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.enabled)
+            .filter_map(|(i, slot)| slot.window().map(|window| (i, window)))
+            .collect();
+        let mut pairs = Vec::new();
+        for (n, (i, a)) in windows.iter().enumerate() {
+            for (j, b) in &windows[n + 1..] {
+                if a.overlaps(b) {
+                    pairs.push((*i, *j));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// When captures of `group` run: each enabled slot capturing it or
+    /// left open to any group. Empty when nothing schedules it.
+    pub fn windows(&self, group: &str) -> Vec<String> {
+        self.time_slots
+            .iter()
+            .filter(|slot| slot.enabled && slot.group.as_deref().is_none_or(|x| x == group))
+            .map(|slot| {
+                let mut window = format!("{} {}-{}", slot.name, slot.start, slot.end);
+                if let Recurrence::Weekly { days } = &slot.recurrence {
+                    let days: Vec<String> = days.iter().map(|x| x.to_string()).collect();
+                    window.push_str(&format!(" {}", days.join(",")));
+                }
+                match &slot.mode {
+                    Some(mode) => format!("{} ({mode})", window.trim()),
+                    None => window.trim().to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Points the slots capturing with `mode` at `to`, or at no mode
+    pub fn retarget_mode(&mut self, mode: &str, to: Option<&str>) {
+        for slot in &mut self.time_slots {
+            if slot.mode.as_deref() == Some(mode) {
+                slot.mode = to.map(str::to_string);
+            }
+        }
+    }
+
+    /// Points the slots capturing `group` at `to`, or at no group
+    pub fn retarget_group(&mut self, group: &str, to: Option<&str>) {
+        for slot in &mut self.time_slots {
+            if slot.group.as_deref() == Some(group) {
+                slot.group = to.map(str::to_string);
+            }
+        }
+    }
+
+    /// Deletes the slots capturing `group`
+    pub fn drop_group(&mut self, group: &str) {
+        self.time_slots
+            .retain(|slot| slot.group.as_deref() != Some(group));
+    }
+
+    /// Ids of the section's fields in Tab order
+    pub fn focus_chain(&self, order: &[Field]) -> Vec<iced::widget::text_input::Id> {
+        self.time_slots
+            .iter()
+            .flat_map(|x| {
+                focus::row(
+                    order,
+                    &[Field::SlotName, Field::SlotStart, Field::SlotEnd],
+                    x.id,
+                )
+            })
+            .collect()
+    }
+
+    /// Start and end problems of every enabled slot, by position
+    pub fn time_errors(&self) -> Vec<(usize, String)> {
+        self.time_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.enabled)
+            .flat_map(|(i, slot)| slot.time_errors().into_iter().map(move |e| (i, e)))
+            .collect()
+    }
+
+    /// `groups` and `modes` are the names slots may refer to. Disabled
+    /// slots aren't checked, as they don't run.
+    pub fn validate(&self, groups: &[String], modes: &[String]) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = self
+            .time_errors()
+            .into_iter()
+            .map(|(i, e)| {
+                ValidationIssue::error(
+                    "Scheduling",
+                    format!("slot {} '{}': {e}", i + 1, self.time_slots[i].name),
+                )
+            })
+            .collect();
+        for (i, slot) in self
+            .time_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.enabled)
+        {
+            // Without its mode a slot has no settings to capture with
+            issues.extend(
+                slot.missing(groups, modes)
+                    .into_iter()
+                    .map(|(label, name)| {
+                        let message = format!(
+                            "{} refers to {label} '{name}', which no longer exists",
+                            self.label(i)
+                        );
+                        match label {
+                            "mode" => ValidationIssue::error("Scheduling", message),
+                            _ => ValidationIssue::warning("Scheduling", message),
+                        }
+                    }),
+            );
+        }
+        issues.extend(self.overlaps().into_iter().map(|(i, j)| {
+            ValidationIssue::warning(
+                "Scheduling",
+                format!("{} overlaps {}", self.label(i), self.label(j)),
+            )
+        }));
+        issues.extend(
+            self.time_slots
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| slot.enabled && slot.wraps_midnight() && !slot.crosses_midnight)
+                .map(|(i, slot)| {
+                    ValidationIssue::warning(
+                        "Scheduling",
+                        format!(
+                            "slot {} '{}' ends before it starts and would cross midnight; confirm it or fix the times",
+                            i + 1,
+                            slot.name
+                        ),
+                    )
+                }),
+        );
+        issues
+    }
+
     pub fn update(&mut self, msg: SchedulingMsg) {
         match msg {
             SchedulingMsg::Add => {
                 self.time_slots.push(TimeSlot::default());
             }
             SchedulingMsg::Change(id, c) => {
-                self.time_slots[id].update(c);
+                if let Some(slot) = self.time_slots.iter_mut().find(|x| x.id == id) {
+                    slot.update(c);
+                }
             }
             SchedulingMsg::Delete(id) => {
-                self.time_slots.remove(id);
+                self.time_slots.retain(|x| x.id != id);
             }
         }
     }
 
-    pub fn view(&self) -> iced::Element<'_, Message> {
-        
+    pub fn view(&self, groups: Vec<String>, modes: Vec<String>) -> iced::Element<'_, Message> {
+        let overlaps = self.overlaps();
         iced::Element::from(
-            iced::widget::row![
-                iced::widget::button("Add Schedule").on_press(SchedulingMsg::Add),
-            ].extend(self.time_slots.iter().enumerate().map(|(i, t)| {
-                iced::widget::column![
-                    iced::widget::button("Delete").on_press(SchedulingMsg::Delete(i)),
-                    iced::Element::from(t.view()).map(move |c| SchedulingMsg::Change(i, c)),
-                ].into()
-            })
-        )).map(|c| Message::Scheduling(c))
+            iced::widget::row![iced::widget::button("Add Schedule").on_press(SchedulingMsg::Add),]
+                .extend(self.time_slots.iter().enumerate().map(|(i, t)| {
+                    let overlapping = overlaps
+                        .iter()
+                        .filter_map(|(a, b)| {
+                            if i == *a {
+                                Some(self.label(*b))
+                            } else if i == *b {
+                                Some(self.label(*a))
+                            } else {
+                                None
+                            }
+                        })
+                        .collect();
+                    iced::widget::column![
+                        iced::widget::button("Delete").on_press(SchedulingMsg::Delete(t.id)),
+                        iced::Element::from(t.view(groups.clone(), modes.clone(), overlapping))
+                            .map(move |c| SchedulingMsg::Change(t.id, c)),
+                    ]
+                    .into()
+                })),
+        )
+        .map(Message::Scheduling)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduling(slots: serde_json::Value) -> Scheduling {
+        serde_json::from_value(serde_json::json!({ "time_slots": slots })).unwrap()
+    }
+
+    fn slot(start: &str, end: &str) -> TimeSlot {
+        TimeSlot {
+            start: start.to_string(),
+            end: end.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn window_past_midnight_runs_into_next_day() {
+        let Some(Window::Repeating(spans)) = slot("23:00", "01:00").window() else {
+            panic!("23:00-01:00 has no window");
+        };
+        assert_eq!(spans.len(), 8);
+        assert_eq!(spans[0], (23 * 3600, DAY + 3600));
+        // Sunday's runs into Monday, at the start of the week
+        assert!(spans.contains(&(6 * DAY + 23 * 3600, WEEK)) && spans.contains(&(0, 3600)));
+        assert_eq!(
+            spans.iter().map(|(from, to)| to - from).sum::<u32>(),
+            7 * 2 * 3600
+        );
+        assert!(slot("23:00", "01:00").wraps_midnight());
+        assert!(!slot("01:00", "23:00").wraps_midnight());
+    }
+
+    #[test]
+    fn overlap_across_midnight() {
+        let overlaps = |a: (&str, &str), b: (&str, &str)| {
+            slot(a.0, a.1)
+                .window()
+                .unwrap()
+                .overlaps(&slot(b.0, b.1).window().unwrap())
+        };
+        assert!(overlaps(("23:00", "01:00"), ("00:30", "02:00")));
+        assert!(overlaps(("22:00", "02:00"), ("23:30", "23:45")));
+        assert!(overlaps(("23:00", "01:00"), ("22:00", "00:30")));
+        assert!(!overlaps(("23:00", "01:00"), ("01:00", "03:00")));
+        assert!(!overlaps(("23:00", "01:00"), ("02:00", "22:00")));
+        let night = scheduling(serde_json::json!([
+            {"name": "night", "start": "23:00", "end": "01:00", "crosses_midnight": true},
+            {"name": "early", "start": "00:30", "end": "02:00"},
+        ]));
+        assert_eq!(night.overlaps(), [(0, 1)]);
+    }
+
+    #[test]
+    fn overlap_table() {
+        use serde_json::json;
+        let weekly = |start: &str, end: &str, days: &[&str]| json!({"name": "w", "start": start, "end": end, "recurrence": {"weekly": {"days": days}}});
+        let daily = |start: &str, end: &str| json!({"name": "d", "start": start, "end": end});
+        let once = |start: &str, end: &str| json!({"name": "o", "start": start, "end": end, "recurrence": "once"});
+        // 2024-05-06 is a Monday
+        let table = [
+            // Half-open: one ending as the other starts doesn't overlap
+            (daily("08:00", "10:00"), daily("10:00", "12:00"), false),
+            (daily("08:00", "10:01"), daily("10:00", "12:00"), true),
+            (daily("08:00", "08:00"), daily("00:00", "23:59"), false),
+            (
+                weekly("08:00", "10:00", &["Mon"]),
+                weekly("09:00", "11:00", &["Tue"]),
+                false,
+            ),
+            (
+                weekly("08:00", "10:00", &["Mon", "Wed"]),
+                weekly("09:00", "11:00", &["Wed"]),
+                true,
+            ),
+            (
+                weekly("23:00", "01:00", &["Sun"]),
+                weekly("00:30", "02:00", &["Mon"]),
+                true,
+            ),
+            (
+                weekly("23:00", "01:00", &["Sat"]),
+                weekly("00:30", "02:00", &["Mon"]),
+                false,
+            ),
+            (
+                weekly("08:00", "10:00", &["Fri"]),
+                daily("09:00", "09:30"),
+                true,
+            ),
+            (
+                weekly("08:00", "10:00", &["Fri"]),
+                daily("10:00", "11:00"),
+                false,
+            ),
+            (
+                once("2024-05-06T08:00", "2024-05-06T10:00"),
+                once("2024-05-06T10:00", "2024-05-06T11:00"),
+                false,
+            ),
+            (
+                once("2024-05-06T08:00", "2024-05-07T10:00"),
+                once("2024-05-07T09:00", "2024-05-07T11:00"),
+                true,
+            ),
+            (
+                once("2024-05-06T08:00", "2024-05-06T10:00"),
+                daily("09:00", "09:30"),
+                true,
+            ),
+            (
+                once("2024-05-06T08:00", "2024-05-06T10:00"),
+                weekly("09:00", "09:30", &["Tue"]),
+                false,
+            ),
+            (
+                once("2024-05-07T08:00", "2024-05-07T10:00"),
+                weekly("09:00", "09:30", &["Tue"]),
+                true,
+            ),
+            (
+                once("2024-05-12T23:30", "2024-05-13T00:30"),
+                weekly("00:00", "01:00", &["Mon"]),
+                true,
+            ),
+            (
+                once("2024-05-01T00:00", "2024-06-01T00:00"),
+                weekly("00:00", "01:00", &["Thu"]),
+                true,
+            ),
+        ];
+        for (a, b, expected) in table {
+            let slots = scheduling(json!([a, b]));
+            let found = if expected { vec![(0, 1)] } else { Vec::new() };
+            assert_eq!(slots.overlaps(), found, "{a} and {b}");
+        }
+    }
+
+    #[test]
+    fn recurrence_round_trips() {
+        use serde_json::json;
+        let slots = json!([
+            {"name": "once", "start": "2024-05-06T08:00", "end": "2024-05-06T10:00", "enabled": true, "recurrence": "once"},
+            {"name": "daily", "start": "08:00", "end": "10:00", "enabled": false},
+            {"name": "weekly", "start": "08:00", "end": "10:00", "enabled": true, "recurrence": {"weekly": {"days": ["Mon", "Sat"]}}},
+        ]);
+        let read = scheduling(slots.clone());
+        let recurrences: Vec<&Recurrence> = read.time_slots.iter().map(|x| &x.recurrence).collect();
+        assert_eq!(
+            recurrences,
+            [
+                &Recurrence::Once,
+                &Recurrence::Daily,
+                &Recurrence::Weekly {
+                    days: vec![chrono::Weekday::Mon, chrono::Weekday::Sat]
+                }
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            json!({ "time_slots": slots })
+        );
+    }
+
+    #[test]
+    fn flat_slot_from_before_recurrence_is_daily() {
+        let old = serde_json::json!([{"name": "morning", "start": "06:00", "end": "09:00"}]);
+        let read = scheduling(old);
+        assert_eq!(read.time_slots[0].recurrence, Recurrence::Daily);
+        assert!(read.time_slots[0].enabled);
+        // Written back flat, with no recurrence, for older readers
+        let written = serde_json::to_value(&read).unwrap();
+        assert_eq!(written["time_slots"][0]["start"], "06:00");
+        assert!(written["time_slots"][0].get("recurrence").is_none());
+    }
+
+    #[test]
+    fn edit_after_deleting_earlier_slot_hits_its_slot() {
+        let mut slots = scheduling(serde_json::json!([
+            {"name": "first", "start": "06:00", "end": "09:00"},
+            {"name": "second", "start": "11:00", "end": "14:00"},
+            {"name": "third", "start": "18:00", "end": "21:00"},
+        ]));
+        let ids: Vec<u64> = slots.time_slots.iter().map(|x| x.id).collect();
+        // As when a rename is still queued behind the delete
+        slots.update(SchedulingMsg::Delete(ids[0]));
+        slots.update(SchedulingMsg::Change(
+            ids[2],
+            ChangeMsg::Name("evening".to_string()),
+        ));
+        slots.update(SchedulingMsg::Change(
+            ids[0],
+            ChangeMsg::Name("gone".to_string()),
+        ));
+        let names: Vec<&str> = slots.time_slots.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, ["second", "evening"]);
+    }
+
+    #[test]
+    fn disabled_slots_are_not_validated() {
+        let slots = |enabled: bool| {
+            scheduling(serde_json::json!([
+                {"name": "broken", "start": "25:00", "end": "09:00", "group": "gone", "enabled": enabled},
+                {"name": "late", "start": "23:00", "end": "01:00", "enabled": enabled},
+                {"name": "am", "start": "06:00", "end": "09:00"},
+                {"name": "early", "start": "00:30", "end": "07:00", "enabled": enabled},
+            ]))
+        };
+        let groups = ["ism".to_string()];
+        let enabled = slots(true).validate(&groups, &[]);
+        assert!(
+            enabled
+                .iter()
+                .any(|x| x.severity == validation::Severity::Error),
+            "{enabled:?}"
+        );
+        assert!(
+            enabled.iter().any(|x| x.message.contains("'gone'")),
+            "{enabled:?}"
+        );
+        assert!(
+            enabled.iter().any(|x| x.message.contains("cross midnight")),
+            "{enabled:?}"
+        );
+        assert!(
+            enabled.iter().any(|x| x.message.contains("overlaps")),
+            "{enabled:?}"
+        );
+        assert_eq!(slots(false).validate(&groups, &[]), []);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::tasks::{self, CaptureTask};
+
+/// Shell a capture script is written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum Shell {
+    #[default]
+    Sh,
+    PowerShell,
+}
+
+impl Shell {
+    pub const ALL: &'static [Shell] = &[Shell::Sh, Shell::PowerShell];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Sh => "sh",
+            Self::PowerShell => "ps1",
+        }
+    }
+
+    /// Quotes `arg` so the shell passes it through verbatim
+    fn quote(self, arg: &str) -> String {
+        match self {
+            Self::Sh => format!("'{}'", arg.replace('\'', r"'\''")),
+            Self::PowerShell => format!("'{}'", arg.replace('\'', "''")),
+        }
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sh => write!(f, "sh"),
+            Self::PowerShell => write!(f, "PowerShell"),
+        }
+    }
+}
+
+/// A script running `command` (the capture binary, e.g.
+/// `python3 rf_recorder_parallel.py`) once per task. The command is used
+/// as written so it can carry an interpreter or extra arguments. Output
+/// files are named by `template`, with `{timestamp}` being the time the
+/// script is rendered.
+pub fn render(
+    tasks: &[CaptureTask],
+    shell: Shell,
+    command: &str,
+    compression: &str,
+    level: Option<u64>,
+    template: &str,
+) -> String {
+    let timestamp = tasks::timestamp();
+    let mut script = match shell {
+        Shell::Sh => "#!/bin/sh\nset -e\n".to_string(),
+        Shell::PowerShell => "$ErrorActionPreference = 'Stop'\n".to_string(),
+    };
+    script.push_str(&format!("# {} capture tasks\n", tasks.len()));
+    // PowerShell needs the call operator to run a quoted or multi-word command
+    let prefix = match shell {
+        Shell::Sh => command.to_string(),
+        Shell::PowerShell => format!("& {command}"),
+    };
+    for task in tasks {
+        script.push_str(&format!(
+            "\n# {} / {} at {} Hz, mode {}",
+            task.group, task.name, task.freq, task.mode
+        ));
+        if let Some(antenna) = &task.antenna {
+            script.push_str(&format!(", antenna {antenna}"));
+        }
+        script.push('\n');
+        let mut line = format!(
+            "{prefix} -f {} -s {} -t {} -g {} -o {}",
+            task.freq,
+            task.sample_rate,
+            task.duration,
+            task.gain,
+            shell.quote(&task.file_name(template, &timestamp)),
+        );
+        if !compression.is_empty() {
+            line.push_str(&format!(" -c {}", shell.quote(compression)));
+        }
+        if let Some(level) = level {
+            line.push_str(&format!(" --compression-level {level}"));
+        }
+        script.push_str(&line);
+        script.push('\n');
+    }
+    script
+}
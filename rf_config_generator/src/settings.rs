@@ -0,0 +1,434 @@
+use std::path::PathBuf;
+
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Message, capture_settings::CaptureSettings, focus, script, templates::GroupTemplate};
+
+/// UI scale factors offered in the settings panel
+const SCALES: &[f64] = &[0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// How many recently opened or saved configs the toolbar lists
+const RECENT_LIMIT: usize = 8;
+
+/// Version of exported settings files, bumped when a setting changes
+/// meaning. Imports of any version take whatever settings they can read.
+const EXPORT_VERSION: u32 = 1;
+
+/// The setting `key` of an imported file, if present. Values this version
+/// can't read are noted in `skipped` and left out.
+fn field<T: DeserializeOwned>(
+    map: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    skipped: &mut Vec<String>,
+) -> Option<T> {
+    let parsed = serde_json::from_value(map.get(key)?.clone()).ok();
+    if parsed.is_none() {
+        skipped.push(key.to_string());
+    }
+    parsed
+}
+
+/// Settings as written by Export Settings
+#[derive(serde::Serialize)]
+struct Exported<'a> {
+    version: u32,
+    #[serde(flatten)]
+    settings: &'a AppSettings,
+}
+
+/// Preferences that persist between runs, stored as JSON in the per-user
+/// config directory. Everything has a default so a missing, unreadable or
+/// partial settings file just falls back to defaults.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub recent_files: Vec<PathBuf>,
+    // Name of an iced theme, None follows the system
+    pub theme: Option<String>,
+    pub scale: f64,
+    pub pretty_json: bool,
+    // Directory file dialogs open in
+    pub workspace_root: Option<PathBuf>,
+    pub tab_order: Vec<focus::Field>,
+    pub show_help: bool,
+    // Capture binary exported scripts call, with any interpreter in front
+    pub capture_command: String,
+    pub script_shell: script::Shell,
+    // Named capture settings to reuse across configs
+    pub capture_presets: BTreeMap<String, CaptureSettings>,
+    // Frequency groups laid out relative to a base, to instantiate anywhere
+    pub group_templates: BTreeMap<String, GroupTemplate>,
+    // Whether Export Settings includes recent files, which rarely make
+    // sense on another machine
+    #[serde(skip)]
+    pub export_recent_files: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            recent_files: Vec::new(),
+            theme: None,
+            scale: 1.0,
+            pretty_json: true,
+            workspace_root: None,
+            tab_order: focus::DEFAULT_TAB_ORDER.to_vec(),
+            show_help: false,
+            capture_command: "python3 rf_recorder_parallel.py".to_string(),
+            script_shell: script::Shell::default(),
+            capture_presets: BTreeMap::new(),
+            group_templates: BTreeMap::new(),
+            export_recent_files: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMsg {
+    Theme(iced::Theme),
+    Scale(f64),
+    PrettyJson(bool),
+    PickWorkspaceRoot,
+    WorkspaceRoot(Option<PathBuf>),
+    MoveField((usize, bool)),
+    RemoveField(usize),
+    AddField(focus::Field),
+    ShowHelp(bool),
+    CaptureCommand(String),
+    ScriptShell(script::Shell),
+    SavePreset((String, Box<CaptureSettings>)),
+    DeletePreset(String),
+    SaveTemplate((String, GroupTemplate)),
+    DeleteTemplate(String),
+    ExportRecentFiles(bool),
+    Export,
+    Import,
+    // Contents of the file picked for import
+    Imported(serde_json::Value),
+}
+
+impl From<SettingsMsg> for Message {
+    fn from(value: SettingsMsg) -> Self {
+        Message::Settings(value)
+    }
+}
+
+impl AppSettings {
+    fn path() -> Option<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "rf_config_generator")?;
+        Some(dirs.config_dir().join("settings.json"))
+    }
+
+    pub fn load() -> Self {
+        let mut settings: Self = Self::path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        settings.recent_files.retain(|x| x.exists());
+        settings
+    }
+
+    /// Puts `path` at the top of the recent files and saves, dropping
+    /// files that have since been deleted
+    pub fn remember(&mut self, path: &std::path::Path) -> Result<(), String> {
+        self.recent_files.retain(|x| x != path && x.exists());
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(RECENT_LIMIT);
+        self.save()
+    }
+
+    /// Drops `path` from the recent files and saves
+    pub fn forget(&mut self, path: &std::path::Path) -> Result<(), String> {
+        self.recent_files.retain(|x| x != path);
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no config directory for this platform")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let serialized = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        crate::utils::write_atomic(&path, serialized.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    /// Takes on the settings in an exported file. Presets, templates and
+    /// recent files are merged into the current ones, a same-named entry
+    /// being replaced; other settings present in the file replace the
+    /// current value. Returns what was imported and what couldn't be.
+    pub fn merge(&mut self, imported: &serde_json::Value) -> String {
+        let Some(map) = imported.as_object() else {
+            return "The file is not a settings export.".to_string();
+        };
+        let mut skipped = Vec::new();
+        let skipped = &mut skipped;
+        if let Some(x) = field(map, "theme", skipped) {
+            self.theme = x;
+        }
+        if let Some(x) = field(map, "scale", skipped) {
+            self.scale = x;
+        }
+        if let Some(x) = field(map, "pretty_json", skipped) {
+            self.pretty_json = x;
+        }
+        if let Some(x) = field(map, "workspace_root", skipped) {
+            self.workspace_root = x;
+        }
+        if let Some(x) = field(map, "tab_order", skipped) {
+            self.tab_order = x;
+        }
+        if let Some(x) = field(map, "show_help", skipped) {
+            self.show_help = x;
+        }
+        if let Some(x) = field(map, "capture_command", skipped) {
+            self.capture_command = x;
+        }
+        if let Some(x) = field(map, "script_shell", skipped) {
+            self.script_shell = x;
+        }
+        let recent: Vec<PathBuf> = field(map, "recent_files", skipped).unwrap_or_default();
+        for path in recent {
+            if !self.recent_files.contains(&path) {
+                self.recent_files.push(path);
+            }
+        }
+        let presets: BTreeMap<String, CaptureSettings> =
+            field(map, "capture_presets", skipped).unwrap_or_default();
+        let templates: BTreeMap<String, GroupTemplate> =
+            field(map, "group_templates", skipped).unwrap_or_default();
+        let summary = format!(
+            "Imported settings with {} capture presets and {} group templates.",
+            presets.len(),
+            templates.len()
+        );
+        self.capture_presets.extend(presets);
+        self.group_templates.extend(templates);
+
+        let version = map
+            .get("version")
+            .and_then(|x| x.as_u64())
+            .unwrap_or_default();
+        let mut notes = vec![summary];
+        if version > EXPORT_VERSION as u64 {
+            notes.push(
+                "They come from a newer version; settings this version doesn't know were left out."
+                    .to_string(),
+            );
+        }
+        if !skipped.is_empty() {
+            notes.push(format!("Could not read: {}.", skipped.join(", ")));
+        }
+        notes.join(" ")
+    }
+
+    fn dialog(&self) -> rfd::AsyncFileDialog {
+        let dialog = rfd::AsyncFileDialog::new().add_filter("settings", &["json"]);
+        match &self.workspace_root {
+            Some(dir) => dialog.set_directory(dir),
+            None => dialog,
+        }
+    }
+
+    pub fn theme(&self) -> iced::Theme {
+        self.theme
+            .as_ref()
+            .and_then(|name| iced::Theme::ALL.iter().find(|x| x.to_string() == *name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Applies the change and writes the settings out, reporting a failed
+    /// write as a warning rather than losing the change
+    pub fn update(&mut self, msg: SettingsMsg) -> iced::Task<Message> {
+        match msg {
+            SettingsMsg::Theme(theme) => self.theme = Some(theme.to_string()),
+            SettingsMsg::Scale(scale) => self.scale = scale,
+            SettingsMsg::PrettyJson(pretty) => self.pretty_json = pretty,
+            SettingsMsg::PickWorkspaceRoot => {
+                return iced::Task::perform(rfd::AsyncFileDialog::new().pick_folder(), |dir| {
+                    match dir {
+                        Some(dir) => {
+                            SettingsMsg::WorkspaceRoot(Some(dir.path().to_path_buf())).into()
+                        }
+                        None => Message::None,
+                    }
+                });
+            }
+            SettingsMsg::WorkspaceRoot(root) => self.workspace_root = root,
+            SettingsMsg::MoveField((i, up)) => {
+                let j = if up { i.checked_sub(1) } else { Some(i + 1) };
+                if let Some(j) = j.filter(|j| *j < self.tab_order.len()) {
+                    self.tab_order.swap(i, j);
+                }
+            }
+            SettingsMsg::RemoveField(i) => {
+                if i < self.tab_order.len() {
+                    self.tab_order.remove(i);
+                }
+            }
+            SettingsMsg::AddField(field) => {
+                if !self.tab_order.contains(&field) {
+                    self.tab_order.push(field);
+                }
+            }
+            SettingsMsg::ShowHelp(show) => self.show_help = show,
+            SettingsMsg::CaptureCommand(command) => self.capture_command = command,
+            SettingsMsg::ScriptShell(shell) => self.script_shell = shell,
+            SettingsMsg::SavePreset((name, preset)) => {
+                self.capture_presets.insert(name, *preset);
+            }
+            SettingsMsg::DeletePreset(name) => {
+                self.capture_presets.remove(&name);
+            }
+            SettingsMsg::SaveTemplate((name, template)) => {
+                self.group_templates.insert(name, template);
+            }
+            SettingsMsg::DeleteTemplate(name) => {
+                self.group_templates.remove(&name);
+            }
+            SettingsMsg::ExportRecentFiles(export) => {
+                self.export_recent_files = export;
+                return iced::Task::none();
+            }
+            SettingsMsg::Export => {
+                let mut exported = self.clone();
+                if !self.export_recent_files {
+                    exported.recent_files.clear();
+                }
+                let dialog = self
+                    .dialog()
+                    .set_file_name("rf_config_generator_settings.json");
+                return iced::Task::perform(
+                    async move {
+                        let path = dialog.save_file().await?;
+                        let exported = Exported {
+                            version: EXPORT_VERSION,
+                            settings: &exported,
+                        };
+                        let written = serde_json::to_string_pretty(&exported)
+                            .map_err(|e| e.to_string())
+                            .and_then(|x| {
+                                crate::utils::write_atomic(path.path(), x.as_bytes())
+                                    .map_err(|e| e.to_string())
+                            });
+                        Some(match written {
+                            Ok(()) => format!("Settings exported to {}", path.path().display()),
+                            Err(e) => format!("Settings could not be exported: {e}"),
+                        })
+                    },
+                    |notice| notice.map_or(Message::None, Message::Notice),
+                );
+            }
+            SettingsMsg::Import => {
+                let dialog = self.dialog();
+                return iced::Task::perform(
+                    async move {
+                        let path = dialog.pick_file().await?;
+                        Some(
+                            std::fs::read(path.path())
+                                .map_err(|e| e.to_string())
+                                .and_then(|x| {
+                                    serde_json::from_slice(&x).map_err(|e| e.to_string())
+                                }),
+                        )
+                    },
+                    |read| match read {
+                        Some(Ok(value)) => SettingsMsg::Imported(value).into(),
+                        Some(Err(e)) => {
+                            Message::Notice(format!("Settings could not be imported: {e}"))
+                        }
+                        None => Message::None,
+                    },
+                );
+            }
+            SettingsMsg::Imported(value) => {
+                let summary = self.merge(&value);
+                return match self.save() {
+                    Ok(()) => iced::Task::done(Message::Notice(summary)),
+                    Err(e) => iced::Task::done(Message::Notice(format!(
+                        "{summary} They could not be saved: {e}"
+                    ))),
+                };
+            }
+        }
+        match self.save() {
+            Ok(()) => iced::Task::none(),
+            Err(e) => {
+                iced::Task::done(Message::Notice(format!("Settings could not be saved: {e}")))
+            }
+        }
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let missing: Vec<focus::Field> = focus::DEFAULT_TAB_ORDER
+            .iter()
+            .filter(|x| !self.tab_order.contains(x))
+            .copied()
+            .collect();
+        let tab_order = iced::widget::column![iced::widget::text("Tab order"),]
+            .extend(self.tab_order.iter().enumerate().map(|(i, field)| {
+                iced::widget::row![
+                    iced::widget::button("Up").on_press(SettingsMsg::MoveField((i, true)).into()),
+                    iced::widget::button("Down")
+                        .on_press(SettingsMsg::MoveField((i, false)).into()),
+                    iced::widget::button("Remove").on_press(SettingsMsg::RemoveField(i).into()),
+                    iced::widget::text(field.to_string()),
+                ]
+                .spacing(4)
+                .into()
+            }))
+            .push(
+                iced::widget::pick_list(missing, None::<focus::Field>, |c| {
+                    SettingsMsg::AddField(c).into()
+                })
+                .placeholder("Add field"),
+            )
+            .spacing(4);
+
+        iced::widget::row![
+            iced::widget::column![
+                iced::widget::text("Theme"),
+                iced::widget::pick_list(iced::Theme::ALL, Some(self.theme()), |c| {
+                    SettingsMsg::Theme(c).into()
+                }),
+                iced::widget::text("Scale"),
+                iced::widget::pick_list(SCALES, Some(self.scale), |c| SettingsMsg::Scale(c).into()),
+                iced::widget::checkbox("Pretty-print saved JSON", self.pretty_json)
+                    .on_toggle(|c| SettingsMsg::PrettyJson(c).into()),
+                iced::widget::text(format!(
+                    "Workspace: {}",
+                    self.workspace_root
+                        .as_ref()
+                        .map(|x| x.display().to_string())
+                        .unwrap_or("not set".to_string())
+                )),
+                iced::widget::row![
+                    iced::widget::button("Choose...")
+                        .on_press(SettingsMsg::PickWorkspaceRoot.into()),
+                    iced::widget::button("Clear").on_press(SettingsMsg::WorkspaceRoot(None).into()),
+                ]
+                .spacing(4),
+                iced::widget::text("Capture command for exported scripts"),
+                iced::widget::text_input("python3 rf_recorder_parallel.py", &self.capture_command)
+                    .on_input(|c| SettingsMsg::CaptureCommand(c).into()),
+                iced::widget::text("Script shell"),
+                iced::widget::pick_list(script::Shell::ALL, Some(self.script_shell), |c| {
+                    SettingsMsg::ScriptShell(c).into()
+                }),
+                iced::widget::row![
+                    iced::widget::button("Export Settings...").on_press(SettingsMsg::Export.into()),
+                    iced::widget::button("Import Settings...").on_press(SettingsMsg::Import.into()),
+                ]
+                .spacing(4),
+                iced::widget::checkbox("Include recent files in exports", self.export_recent_files)
+                    .on_toggle(|c| SettingsMsg::ExportRecentFiles(c).into()),
+            ]
+            .spacing(6),
+            tab_order,
+        ]
+        .spacing(30)
+        .into()
+    }
+}
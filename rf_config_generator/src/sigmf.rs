@@ -0,0 +1,234 @@
+use serde_json::{Value, json};
+
+use crate::{collection_modes::CollectionMode, frequency::Frequency};
+
+/// SigMF version written
+const VERSION: &str = "1.0.0";
+
+/// Namespace of the fields SigMF has no core name for, declared as an
+/// optional extension so other readers skip them
+const NAMESPACE: &str = "rf_capture";
+
+/// Version of the [`NAMESPACE`] extension, raised when its fields change.
+/// Unrelated to the SigMF version.
+const EXTENSION_VERSION: &str = "1.0.0";
+
+/// What a SigMF recording says that a config can use
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub description: String,
+    /// Datatype in the config's terms, e.g. `cf32`, without the byte order
+    pub datatype: String,
+    pub sample_rate: f64,
+    /// Center frequency of each capture segment, in order, without repeats
+    pub frequencies: Vec<f64>,
+    /// Only present in files this tool wrote, SigMF having no core gain
+    pub gain: Option<f64>,
+}
+
+/// SigMF datatype of a config datatype. Multi-byte samples are written
+/// little-endian; 8-bit ones have no byte order.
+fn sigmf_datatype(datatype: &str) -> String {
+    match datatype {
+        "ci8" | "ri8" | "cu8" | "ru8" => datatype.to_string(),
+        _ => format!("{datatype}_le"),
+    }
+}
+
+/// `.sigmf-meta` contents for a recording of `freq` with the collection
+/// mode `mode_name`, samples written as `datatype`. A range is described
+/// by its first frequency, and a gain sweep by its first gain, as each
+/// step is a recording of its own. Mode, duration and antenna go in the
+/// `rf_capture` extension.
+pub fn to_sigmf(freq: &Frequency, mode_name: &str, mode: &CollectionMode, datatype: &str) -> Value {
+    let gain = mode.gains().first().copied().unwrap_or(mode.gain);
+    let description = match freq.description.trim() {
+        "" => freq.name.clone(),
+        description => format!("{}: {description}", freq.name),
+    };
+    let mut global = json!({
+        "core:datatype": sigmf_datatype(datatype),
+        "core:sample_rate": mode.sample_rate,
+        "core:version": VERSION,
+        "core:description": description,
+        "core:recorder": "rf_config_generator",
+        "core:extensions": [{ "name": NAMESPACE, "version": EXTENSION_VERSION, "optional": true }],
+        "rf_capture:mode": mode_name,
+        "rf_capture:duration": mode.duration,
+    });
+    if let Some(antenna) = &mode.antenna {
+        global["rf_capture:antenna"] = antenna.clone().into();
+    }
+    json!({
+        "global": global,
+        "captures": [{
+            "core:sample_start": 0,
+            "core:frequency": freq.freq,
+            "rf_capture:gain": gain,
+        }],
+        "annotations": [],
+    })
+}
+
+/// Reads the parts of a `.sigmf-meta` document a config can take. Fails
+/// when the sample rate or every capture's center frequency is missing,
+/// as there's nothing to capture with then.
+pub fn from_sigmf(meta: &Value) -> Result<Recording, String> {
+    let global = meta
+        .get("global")
+        .and_then(Value::as_object)
+        .ok_or("there is no global object")?;
+    let sample_rate = global
+        .get("core:sample_rate")
+        .and_then(Value::as_f64)
+        .ok_or("the global object has no core:sample_rate")?;
+    let datatype = global
+        .get("core:datatype")
+        .and_then(Value::as_str)
+        .ok_or("the global object has no core:datatype")?;
+    let captures = meta
+        .get("captures")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut frequencies: Vec<f64> = Vec::new();
+    for freq in captures
+        .iter()
+        .filter_map(|x| x.get("core:frequency").and_then(Value::as_f64))
+    {
+        if !frequencies.contains(&freq) {
+            frequencies.push(freq);
+        }
+    }
+    if frequencies.is_empty() {
+        return Err("no capture segment has a core:frequency".to_string());
+    }
+    let gain = captures
+        .iter()
+        .chain(std::iter::once(&meta["global"]))
+        .find_map(|x| x.get("rf_capture:gain").and_then(Value::as_f64));
+    Ok(Recording {
+        description: global
+            .get("core:description")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        datatype: datatype
+            .trim_end_matches("_le")
+            .trim_end_matches("_be")
+            .to_string(),
+        sample_rate,
+        frequencies,
+        gain,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example recording from the SigMF specification
+    const SPEC_EXAMPLE: &str = r#"{
+        "global": {
+            "core:datatype": "cf32_le",
+            "core:sample_rate": 1000000,
+            "core:hw": "PlutoSDR with 915 MHz whip antenna",
+            "core:author": "Art Vandelay",
+            "core:version": "1.0.0"
+        },
+        "captures": [
+            {
+                "core:sample_start": 0,
+                "core:frequency": 915000000,
+                "core:datetime": "2020-06-23T01:23:45.678Z"
+            }
+        ],
+        "annotations": [
+            {
+                "core:sample_start": 100000,
+                "core:sample_count": 120000,
+                "core:freq_lower_edge": 914995000.0,
+                "core:freq_upper_edge": 915005000.0,
+                "core:label": "ASK"
+            }
+        ]
+    }"#;
+
+    /// A recording retuned mid-way, as GNU Radio's SigMF sink writes one,
+    /// with the first frequency revisited at the end
+    const RETUNED: &str = r#"{
+        "global": {
+            "core:datatype": "ci16_le",
+            "core:sample_rate": 2400000.0,
+            "core:version": "1.0.0",
+            "core:description": "ADS-B and ISM survey",
+            "core:recorder": "GNU Radio 3.10.9.2",
+            "core:extensions": []
+        },
+        "captures": [
+            {"core:sample_start": 0, "core:frequency": 1090000000.0},
+            {"core:sample_start": 24000000, "core:frequency": 433920000.0},
+            {"core:sample_start": 48000000, "core:frequency": 1090000000.0}
+        ],
+        "annotations": []
+    }"#;
+
+    #[test]
+    fn reads_spec_example() {
+        let recording = from_sigmf(&serde_json::from_str(SPEC_EXAMPLE).unwrap()).unwrap();
+        assert_eq!(
+            recording,
+            Recording {
+                description: String::new(),
+                datatype: "cf32".to_string(),
+                sample_rate: 1e6,
+                frequencies: vec![915e6],
+                gain: None,
+            }
+        );
+    }
+
+    #[test]
+    fn reads_each_frequency_once() {
+        let recording = from_sigmf(&serde_json::from_str(RETUNED).unwrap()).unwrap();
+        assert_eq!(recording.datatype, "ci16");
+        assert_eq!(recording.description, "ADS-B and ISM survey");
+        assert_eq!(recording.sample_rate, 2.4e6);
+        assert_eq!(recording.frequencies, [1090e6, 433.92e6]);
+    }
+
+    #[test]
+    fn refuses_recording_without_frequency() {
+        let mut meta: Value = serde_json::from_str(SPEC_EXAMPLE).unwrap();
+        meta["captures"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("core:frequency");
+        assert!(from_sigmf(&meta).is_err());
+        assert!(from_sigmf(&json!({"captures": []})).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut freq = Frequency::new(0);
+        freq.name = "ism".to_string();
+        freq.freq = 433.92e6;
+        freq.description = "remotes".to_string();
+        let mut mode = CollectionMode::default();
+        mode.sample_rate = 2e6;
+        mode.duration = 60.0;
+        mode.gain = 35.0;
+        let meta = to_sigmf(&freq, "survey", &mode, "ci16");
+        assert_eq!(meta["global"]["core:datatype"], "ci16_le");
+        assert_eq!(
+            from_sigmf(&meta).unwrap(),
+            Recording {
+                description: "ism: remotes".to_string(),
+                datatype: "ci16".to_string(),
+                sample_rate: 2e6,
+                frequencies: vec![433.92e6],
+                gain: Some(35.0),
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+/// A single capture: one frequency recorded with one concrete set of SDR
+/// settings. Produced by [`crate::config::Config::expand_tasks`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CaptureTask {
+    pub group: String,
+    pub name: String,
+    pub freq: f64,
+    pub mode: String,
+    pub sample_rate: f64,
+    pub duration: f64,
+    pub gain: f64,
+    pub antenna: Option<String>,
+}
+
+/// Output file name used when the config sets no template
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{group}_{name}_{mode}_{hz}Hz_{gain}dB.dat";
+
+/// Tokens a file name template can use
+pub const FILENAME_TOKENS: &[&str] = &[
+    "name",
+    "group",
+    "mode",
+    "freq_mhz",
+    "hz",
+    "gain",
+    "timestamp",
+];
+
+/// Time of an export as `{timestamp}` spells it, free of characters file
+/// names can't hold
+pub fn timestamp() -> String {
+    chrono::Local::now().format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// The `{...}` tokens in `template` that aren't [`FILENAME_TOKENS`]
+pub fn unknown_tokens(template: &str) -> Vec<&str> {
+    let mut unknown = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|x| open + x) else {
+            break;
+        };
+        let token = &rest[open + 1..close];
+        if !FILENAME_TOKENS.contains(&token) {
+            unknown.push(token);
+        }
+        rest = &rest[close + 1..];
+    }
+    unknown
+}
+
+impl CaptureTask {
+    /// Output file of the task, rendered from `template` (see
+    /// [`FILENAME_TOKENS`]) with `timestamp` standing in for
+    /// `{timestamp}`. Unknown tokens are copied as is, and the result is
+    /// made safe as a file name on every platform.
+    pub fn file_name(&self, template: &str, timestamp: &str) -> String {
+        let mut name = String::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            name.push_str(&rest[..open]);
+            let Some(close) = rest[open..].find('}').map(|x| open + x) else {
+                break;
+            };
+            let replacement = match &rest[open + 1..close] {
+                "name" => Some(self.name.clone()),
+                "group" => Some(self.group.clone()),
+                "mode" => Some(self.mode.clone()),
+                "freq_mhz" => Some((self.freq / 1e6).to_string()),
+                "hz" => Some(self.freq.to_string()),
+                "gain" => Some(self.gain.to_string()),
+                "timestamp" => Some(timestamp.to_string()),
+                _ => None,
+            };
+            name.push_str(replacement.as_deref().unwrap_or(&rest[open..=close]));
+            rest = &rest[close + 1..];
+        }
+        name.push_str(rest);
+        crate::utils::sanitize_name(&name)
+    }
+}
+
+/// `tasks` as CSV, one row per capture, `when` giving the schedule of a
+/// group. Fields holding a comma, quote or line break are quoted.
+pub fn csv(tasks: &[CaptureTask], when: impl Fn(&str) -> String) -> String {
+    fn field(value: String) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value
+        }
+    }
+    let mut csv = String::from("group,name,freq_hz,sample_rate,duration,gain,mode,schedule\n");
+    for task in tasks {
+        let fields = [
+            task.group.clone(),
+            task.name.clone(),
+            task.freq.to_string(),
+            task.sample_rate.to_string(),
+            task.duration.to_string(),
+            task.gain.to_string(),
+            task.mode.clone(),
+            when(&task.group),
+        ];
+        csv.push_str(&fields.map(field).join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Most tasks the preview lists; the rest are only counted
+const PREVIEW_LIMIT: usize = 500;
+
+/// Table of `tasks` in order, `when` giving the schedule of a group
+pub fn view<'a>(
+    tasks: &[CaptureTask],
+    when: impl Fn(&str) -> String,
+) -> iced::Element<'a, crate::Message> {
+    let row = |cells: [String; 10]| {
+        let widths = [50, 120, 140, 160, 120, 120, 80, 60, 80, 200];
+        iced::widget::row(
+            cells
+                .into_iter()
+                .zip(widths)
+                .map(|(cell, width)| iced::widget::text(cell).width(width).into()),
+        )
+        .spacing(10)
+    };
+    let header = row([
+        "#",
+        "group",
+        "name",
+        "frequency",
+        "mode",
+        "sample rate",
+        "duration",
+        "gain",
+        "antenna",
+        "when",
+    ]
+    .map(str::to_string));
+    let rows = tasks
+        .iter()
+        .take(PREVIEW_LIMIT)
+        .enumerate()
+        .map(|(i, task)| {
+            row([
+                (i + 1).to_string(),
+                task.group.clone(),
+                task.name.clone(),
+                crate::frequency::format_hz(task.freq),
+                task.mode.clone(),
+                format!("{} S/s", task.sample_rate),
+                format!("{} s", task.duration),
+                format!("{} dB", task.gain),
+                task.antenna.clone().unwrap_or("default".to_string()),
+                when(&task.group),
+            ])
+            .into()
+        });
+    iced::widget::column![
+        iced::widget::text(format!(
+            "{} capture tasks, in the order they run",
+            tasks.len()
+        )),
+        header,
+    ]
+    .extend(rows)
+    .push_maybe(
+        (tasks.len() > PREVIEW_LIMIT)
+            .then(|| iced::widget::text(format!("... and {} more", tasks.len() - PREVIEW_LIMIT))),
+    )
+    .spacing(4)
+    .into()
+}
@@ -0,0 +1,135 @@
+use crate::{
+    Message,
+    frequency::{self, Frequency},
+};
+
+/// One frequency of a group template, placed relative to the base
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TemplateEntry {
+    // May use the rename tokens, e.g. "{mhz} MHz"
+    pub name: String,
+    // Hz above the base, negative for below
+    pub offset: f64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    // Sweep from `offset` up to `stop` (also relative to the base)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<frequency::Range>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// A frequency group laid out relative to a base frequency, kept in the
+/// app settings and turned into a concrete group for any base
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct GroupTemplate {
+    pub entries: Vec<TemplateEntry>,
+}
+
+impl GroupTemplate {
+    /// Template of a group, relative to its first frequency so that
+    /// frequency lands on the base when instantiated
+    pub fn from_group(freqs: &[Frequency]) -> Self {
+        let base = freqs.first().map_or(0.0, |x| x.freq);
+        Self {
+            entries: freqs
+                .iter()
+                .map(|x| TemplateEntry {
+                    name: x.name.clone(),
+                    offset: x.freq - base,
+                    description: x.description.clone(),
+                    range: x.range.map(|range| frequency::Range {
+                        stop: range.stop - base,
+                        ..range
+                    }),
+                    tags: x.tags.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Concrete frequencies around `base`. Names are rendered as rename
+    /// templates, numbering entries from 1.
+    pub fn instantiate(&self, base: f64) -> Vec<Frequency> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mut freq = Frequency::new(rand::random());
+                freq.freq = base + entry.offset;
+                freq.description = entry.description.clone();
+                freq.range = entry.range.map(|range| frequency::Range {
+                    stop: base + range.stop,
+                    ..range
+                });
+                freq.tags = entry.tags.clone();
+                freq.name = frequency::render_name(&entry.name, i + 1, &freq);
+                freq
+            })
+            .collect()
+    }
+}
+
+/// Saving groups as templates and creating groups from them
+#[derive(Debug, Clone)]
+pub enum TemplateMsg {
+    // Stores the group as a template under its own name
+    SaveGroup(String),
+    Select(String),
+    Base(String),
+    Name(String),
+    Instantiate,
+    Delete,
+}
+
+impl From<TemplateMsg> for Message {
+    fn from(value: TemplateMsg) -> Self {
+        Message::Template(value)
+    }
+}
+
+/// Inputs of the template controls
+#[derive(Debug, Default)]
+pub struct TemplatePanel {
+    pub selected: Option<String>,
+    // Base frequency as typed, units allowed
+    pub base: String,
+    // Name of the new group, the template's when blank
+    pub name: String,
+}
+
+impl TemplatePanel {
+    pub fn base(&self) -> Option<f64> {
+        frequency::parse_frequency(&self.base)
+    }
+
+    pub fn view<'a>(&'a self, names: Vec<String>) -> iced::Element<'a, Message> {
+        let selected = self.selected.is_some();
+        let base = self.base();
+        iced::widget::row![
+            iced::widget::text("Templates"),
+            iced::widget::pick_list(names, self.selected.clone(), |c| TemplateMsg::Select(c)
+                .into())
+            .placeholder("choose template"),
+            iced::widget::text_input("base frequency, e.g. 98.1M", &self.base)
+                .on_input(|c| TemplateMsg::Base(c).into())
+                .style(if base.is_some() || self.base.is_empty() {
+                    iced::widget::text_input::default
+                } else {
+                    crate::validation::invalid_input
+                })
+                .width(200),
+            iced::widget::text_input("group name", &self.name)
+                .on_input(|c| TemplateMsg::Name(c).into())
+                .width(160),
+            iced::widget::button("Instantiate").on_press_maybe(
+                (selected && base.is_some()).then_some(TemplateMsg::Instantiate.into())
+            ),
+            iced::widget::button("Delete")
+                .on_press_maybe(selected.then_some(TemplateMsg::Delete.into())),
+        ]
+        .spacing(10)
+        .into()
+    }
+}
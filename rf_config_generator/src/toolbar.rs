@@ -1,23 +1,37 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use iced::widget;
-use rfd::FileDialog;
+use serde::Deserialize;
+use sha2::Digest;
 
-use crate::{config::{self, Config}, Message};
+use crate::{
+    Message,
+    config::Config,
+    file_format::{FileFormat, FormatError},
+    migrate,
+    script::Shell,
+    settings::{AppSettings, SettingsMsg},
+    sigmf, utils,
+};
 
 pub enum ToolbarErr {
-    Serialize(serde_json::Error),
+    Serialize(FormatError),
+    // The serialized config doesn't read back, e.g. a NaN saved as null
+    RoundTrip(FormatError),
     NoFileSelected,
     FileIoError(std::io::Error),
 }
 
 impl std::fmt::Display for ToolbarErr {
-
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Serialize(e) => write!(f,"{}", e),
+            Self::Serialize(e) => write!(f, "{}", e),
+            Self::RoundTrip(e) => write!(
+                f,
+                "the config would not open again ({e}); is a value not a finite number?"
+            ),
             Self::NoFileSelected => write!(f, "No File Selected"),
-            Self::FileIoError(e) => write!(f,"{}", e),
+            Self::FileIoError(e) => write!(f, "{}", e),
         }
     }
 }
@@ -25,13 +39,52 @@ impl std::fmt::Display for ToolbarErr {
 #[derive(Debug, Clone)]
 pub enum ToolbarMsg {
     OpenFile,
-    SaveFile(Option<Config>),
+    // Opens a file from the recent files list
+    OpenRecent(PathBuf),
+    SaveFile(Option<Box<Config>>),
+    // Writes a copy holding only the enabled frequencies
+    ExportSelection(Option<Box<Config>>),
+    // Writes a script running every capture task
+    ExportScript(Option<(Shell, String)>),
+    // Writes every capture task as CSV
+    ExportPlan(Option<String>),
+    // Writes a GNU Radio flowgraph, with the file name to suggest
+    ExportFlowgraph((String, String)),
+    // Writes SigMF metadata, with the file name to suggest
+    ExportSigmf((String, String)),
+    // Reads a .sigmf-meta file into the config
+    ImportSigmf,
+    // Recording read from a .sigmf-meta file, with the file's name
+    // without its extension
+    ImportedSigmf((String, Box<sigmf::Recording>)),
+    // Reads a CSV file of frequencies into the named group
+    ImportCsv(String),
+    // Group and contents of the CSV file picked for it
+    ImportedCsv((String, String)),
     NewFile,
+    // Reloads the config from the file it came from, dropping unsaved
+    // changes; the app fills in the path once the user confirms
+    Revert(Option<PathBuf>),
+    ConfirmRevert(bool),
+    // Answer to dropping unsaved changes for New or Open
+    ConfirmDiscard(bool),
+    // Answer to saving despite validation warnings; false closes the list
+    ConfirmSave(bool),
     // Full deserialized config from opened file
-    OpenedFile(Config),
-    // Path to file to save to
-    SavedFile,
-    Error,
+    OpenedFile((Box<Config>, PathBuf)),
+    // Opened file that doesn't look like a config, and why; held until the
+    // user confirms or cancels the load
+    SuspiciousFile((Box<Config>, PathBuf, String)),
+    ConfirmOpen(bool),
+    // RFC3339 time the save was made at, and where to
+    SavedFile((String, PathBuf)),
+    Exported,
+    // Non-fatal problem the user should know about
+    Warning(String),
+    // A file dialog was closed without picking a file
+    Cancelled,
+    // A file operation failed, and why, for the status bar
+    Error(String),
 }
 
 impl From<ToolbarMsg> for Message {
@@ -43,74 +96,410 @@ impl From<ToolbarMsg> for Message {
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Toolbar;
 
+/// Entry of the recent files list, shown by file name and directory
+#[derive(Debug, Clone, PartialEq)]
+struct RecentFile(PathBuf);
+
+impl std::fmt::Display for RecentFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.parent() {
+            Some(dir) => write!(f, "{} ({})", file_name(&self.0), dir.display()),
+            None => write!(f, "{}", self.0.display()),
+        }
+    }
+}
+
+/// Outcome of reading a config file
+enum Opened {
+    // Along with a warning if its checksum sidecar does not match
+    Config(Config, PathBuf, Option<String>),
+    Suspicious(Config, PathBuf, String),
+    // Couldn't be read as a config, and why
+    Unreadable(String),
+}
+
 impl Toolbar {
-    pub fn view(&self) -> widget::Row<Message> {
+    /// `can_revert` when the config has a file and unsaved changes.
+    /// `recent` are the files offered for reopening, newest first.
+    pub fn view(
+        &self,
+        show_help: bool,
+        can_revert: bool,
+        recent: &[PathBuf],
+    ) -> widget::Row<'_, Message> {
         widget::row![
             widget::button("Open File...").on_press(Message::from(ToolbarMsg::OpenFile)),
+            widget::pick_list(
+                recent.iter().cloned().map(RecentFile).collect::<Vec<_>>(),
+                None::<RecentFile>,
+                |c| Message::from(ToolbarMsg::OpenRecent(c.0)),
+            )
+            .placeholder("Recent"),
             widget::button("Save To File...").on_press(Message::from(ToolbarMsg::SaveFile(None))),
             widget::button("New Config").on_press(Message::from(ToolbarMsg::NewFile)),
+            widget::button("Revert")
+                .on_press_maybe(can_revert.then_some(Message::from(ToolbarMsg::Revert(None)))),
+            widget::button("Export Selection...")
+                .on_press(Message::from(ToolbarMsg::ExportSelection(None))),
+            widget::button("Export Script...")
+                .on_press(Message::from(ToolbarMsg::ExportScript(None))),
+            widget::button("Export Plan...").on_press(Message::from(ToolbarMsg::ExportPlan(None))),
+            widget::button("Export Flowgraph or SigMF...")
+                .on_press(Message::from(crate::grc::FlowgraphMsg::Open)),
+            widget::button("Import SigMF...").on_press(Message::from(ToolbarMsg::ImportSigmf)),
+            widget::button("Settings").on_press(Message::ToggleSettings),
+            widget::button("Help").on_press(SettingsMsg::ShowHelp(!show_help).into()),
         ]
     }
 
-    pub fn update(&mut self, message: ToolbarMsg) -> iced::Task<Message> {
+    /// Opened and saved files are added to the settings' recent files
+    pub fn update(
+        &mut self,
+        message: ToolbarMsg,
+        settings: &mut AppSettings,
+    ) -> iced::Task<Message> {
+        let dir = settings.workspace_root.clone();
+        let pretty = settings.pretty_json;
         match message {
             ToolbarMsg::OpenFile => {
-                iced::Task::perform(Self::open_file(), |c| {
-                    match c {
-                        Some(conf) => ToolbarMsg::OpenedFile(conf),
-                        None => ToolbarMsg::Error,
-                    }.into()
-                })
+                iced::Task::perform(Self::open_file(dir), |c| c).then(Self::opened)
+            }
+            ToolbarMsg::OpenRecent(path) => {
+                if path.exists() {
+                    return iced::Task::perform(async move { Self::read_file(path) }, |c| c)
+                        .then(Self::opened);
+                }
+                let error = format!(
+                    "{} no longer exists and was removed from the recent files.",
+                    path.display()
+                );
+                let _ = settings.forget(&path);
+                iced::Task::done(ToolbarMsg::Error(error).into())
+            }
+            ToolbarMsg::OpenedFile((_, path)) | ToolbarMsg::SavedFile((_, path)) => {
+                match settings.remember(&path) {
+                    Ok(()) => iced::Task::none(),
+                    Err(e) => iced::Task::done(Message::Notice(format!(
+                        "Recent files could not be saved: {e}"
+                    ))),
+                }
+            }
+            ToolbarMsg::Revert(Some(path)) => {
+                iced::Task::perform(async move { Self::read_file(path) }, |c| c).then(Self::opened)
             }
             ToolbarMsg::SaveFile(conf) => {
                 if let Some(conf) = conf {
-                    iced::Task::perform(Self::save_file(conf), |res| {
-                        if let Err(e) = res {
-                            eprintln!("Error saving file! {e}");
-                            return Message::from(ToolbarMsg::Error);
-                        } else {
-                            return Message::from(ToolbarMsg::SavedFile);
-                        }
+                    iced::Task::perform(Self::save_file(*conf, dir, pretty), |res| match res {
+                        Ok(saved) => Message::from(ToolbarMsg::SavedFile(saved)),
+                        Err(e) => failed("Saving", e),
                     })
                 } else {
                     iced::Task::none()
                 }
             }
-            _ => iced::Task::none()
+            ToolbarMsg::ExportSelection(Some(conf)) => {
+                iced::Task::perform(Self::save_file(*conf, dir, pretty), |res| match res {
+                    Ok(_) => Message::from(ToolbarMsg::Exported),
+                    Err(e) => failed("Exporting the selection", e),
+                })
+            }
+            ToolbarMsg::ExportScript(Some((shell, script))) => {
+                iced::Task::perform(Self::save_script(shell, script, dir), |res| match res {
+                    Ok(()) => Message::from(ToolbarMsg::Exported),
+                    Err(e) => failed("Exporting the script", e),
+                })
+            }
+            ToolbarMsg::ExportPlan(Some(plan)) => {
+                iced::Task::perform(Self::save_plan(plan, dir), |res| match res {
+                    Ok(()) => Message::from(ToolbarMsg::Exported),
+                    Err(e) => failed("Exporting the plan", e),
+                })
+            }
+            ToolbarMsg::ExportFlowgraph((name, flowgraph)) => iced::Task::perform(
+                Self::save_flowgraph(name, flowgraph, dir),
+                |res| match res {
+                    Ok(()) => Message::from(ToolbarMsg::Exported),
+                    Err(e) => failed("Exporting the flowgraph", e),
+                },
+            ),
+            ToolbarMsg::ExportSigmf((name, meta)) => {
+                iced::Task::perform(Self::save_sigmf(name, meta, dir), |res| match res {
+                    Ok(()) => Message::from(ToolbarMsg::Exported),
+                    Err(e) => failed("Exporting the SigMF metadata", e),
+                })
+            }
+            ToolbarMsg::ImportSigmf => iced::Task::perform(Self::import_sigmf(dir), |c| c),
+            ToolbarMsg::ImportCsv(group) => {
+                iced::Task::perform(Self::import_csv(group, dir), |c| c)
+            }
+            _ => iced::Task::none(),
         }
     }
 
-    async fn open_file() -> Option<Config> {
-        let f = rfd::AsyncFileDialog::new()
-            .add_filter("config", &["json"])
-            .pick_file()
-            .await?
-            .read()
+    async fn save_sigmf(
+        name: String,
+        meta: String,
+        dir: Option<PathBuf>,
+    ) -> Result<(), ToolbarErr> {
+        let f = dialog(dir)
+            .add_filter("SigMF metadata", &["sigmf-meta"])
+            .set_file_name(name)
+            .save_file()
             .await;
-        
-        match serde_json::from_slice(&f) {
-            Ok(conf) => Some(conf),
+        let Some(f) = f else {
+            return Err(ToolbarErr::NoFileSelected);
+        };
+        utils::write_atomic(f.path(), meta.as_bytes()).map_err(ToolbarErr::FileIoError)
+    }
+
+    async fn import_sigmf(dir: Option<PathBuf>) -> Message {
+        let Some(f) = dialog(dir)
+            .add_filter("SigMF metadata", &["sigmf-meta"])
+            .pick_file()
+            .await
+        else {
+            return ToolbarMsg::Cancelled.into();
+        };
+        let recording = std::fs::read(f.path())
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+            .and_then(|meta| sigmf::from_sigmf(&meta));
+        let name = file_name(f.path());
+        match recording {
+            Ok(recording) => {
+                let name = name.trim_end_matches(".sigmf-meta").to_string();
+                ToolbarMsg::ImportedSigmf((name, Box::new(recording))).into()
+            }
+            Err(e) => ToolbarMsg::Error(format!("{name} could not be imported: {e}.")).into(),
+        }
+    }
+
+    async fn import_csv(group: String, dir: Option<PathBuf>) -> Message {
+        let Some(f) = dialog(dir).add_filter("CSV", &["csv"]).pick_file().await else {
+            return ToolbarMsg::Cancelled.into();
+        };
+        match std::fs::read(f.path()) {
+            Ok(bytes) => {
+                ToolbarMsg::ImportedCsv((group, String::from_utf8_lossy(&bytes).into_owned()))
+                    .into()
+            }
             Err(e) => {
-                eprintln!("Error opening file {e}");
-                None
+                ToolbarMsg::Error(format!("{} could not be read: {e}.", file_name(f.path()))).into()
             }
         }
     }
 
-    async fn save_file(conf: Config) -> Result<(), ToolbarErr> {
-        let f = rfd::AsyncFileDialog::new()
-            .add_filter("config", &["json"])
+    async fn save_flowgraph(
+        name: String,
+        flowgraph: String,
+        dir: Option<PathBuf>,
+    ) -> Result<(), ToolbarErr> {
+        let f = dialog(dir)
+            .add_filter("GNU Radio Companion", &["grc"])
+            .set_file_name(name)
+            .save_file()
+            .await;
+        let Some(f) = f else {
+            return Err(ToolbarErr::NoFileSelected);
+        };
+        utils::write_atomic(f.path(), flowgraph.as_bytes()).map_err(ToolbarErr::FileIoError)
+    }
+
+    async fn save_plan(plan: String, dir: Option<PathBuf>) -> Result<(), ToolbarErr> {
+        let f = dialog(dir)
+            .add_filter("CSV", &["csv"])
+            .set_file_name("capture_plan.csv")
+            .save_file()
+            .await;
+        let Some(f) = f else {
+            return Err(ToolbarErr::NoFileSelected);
+        };
+        utils::write_atomic(f.path(), plan.as_bytes()).map_err(ToolbarErr::FileIoError)
+    }
+
+    async fn save_script(
+        shell: Shell,
+        script: String,
+        dir: Option<PathBuf>,
+    ) -> Result<(), ToolbarErr> {
+        let f = dialog(dir)
+            .add_filter(shell.to_string(), &[shell.extension()])
+            .set_file_name(format!("capture.{}", shell.extension()))
             .save_file()
             .await;
+        let Some(f) = f else {
+            return Err(ToolbarErr::NoFileSelected);
+        };
+        utils::write_atomic(f.path(), script.as_bytes()).map_err(ToolbarErr::FileIoError)?;
+        set_executable(f.path()).map_err(ToolbarErr::FileIoError)
+    }
+
+    /// Result messages for a read config file
+    fn opened(opened: Option<Opened>) -> iced::Task<Message> {
+        match opened {
+            Some(Opened::Suspicious(conf, path, reason)) => {
+                iced::Task::done(ToolbarMsg::SuspiciousFile((Box::new(conf), path, reason)).into())
+            }
+            Some(Opened::Config(conf, path, None)) => {
+                iced::Task::done(ToolbarMsg::OpenedFile((Box::new(conf), path)).into())
+            }
+            Some(Opened::Config(conf, path, Some(warning))) => {
+                iced::Task::done(ToolbarMsg::OpenedFile((Box::new(conf), path)).into())
+                    .chain(iced::Task::done(ToolbarMsg::Warning(warning).into()))
+            }
+            Some(Opened::Unreadable(reason)) => iced::Task::done(ToolbarMsg::Error(reason).into()),
+            None => iced::Task::done(ToolbarMsg::Cancelled.into()),
+        }
+    }
 
-        let Some(f) = f else { return Err(ToolbarErr::NoFileSelected); };
-        let serialized = serde_json::to_string_pretty(&conf);
+    async fn open_file(dir: Option<PathBuf>) -> Option<Opened> {
+        let extensions: Vec<&str> = FileFormat::ALL
+            .iter()
+            .flat_map(|x| x.extensions())
+            .copied()
+            .collect();
+        let handle = dialog(dir)
+            .add_filter("config", &extensions)
+            .pick_file()
+            .await?;
+        Self::read_file(handle.path().to_path_buf())
+    }
 
-        let Ok(serialized) = serialized else { return Err(ToolbarErr::Serialize(serialized.unwrap_err())); };
+    fn read_file(path: PathBuf) -> Option<Opened> {
+        let unreadable = |e: &dyn std::fmt::Display| {
+            Some(Opened::Unreadable(format!(
+                "{} could not be opened: {e}.",
+                file_name(&path)
+            )))
+        };
+        let f = match std::fs::read(&path) {
+            Ok(f) => f,
+            Err(e) => return unreadable(&e),
+        };
 
-        match f.write(serialized.as_bytes()).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(ToolbarErr::FileIoError(e))
+        let raw = match FileFormat::from_path(&path).read(&f) {
+            Ok(raw) => raw,
+            Err(e) => return unreadable(&e),
+        };
+        // Older formats are upgraded on the way in
+        match migrate::read(raw) {
+            Ok((conf, raw)) => match conf.suspicion(&raw) {
+                Some(reason) => Some(Opened::Suspicious(
+                    conf,
+                    path.clone(),
+                    format!("{} may not be a config file: {reason}.", file_name(&path)),
+                )),
+                None => {
+                    let warning = verify_checksum(&path, &f);
+                    Some(Opened::Config(conf, path, warning))
+                }
+            },
+            Err(e) => unreadable(&e),
         }
     }
+
+    /// Stamps the config with the save time, which is returned on success
+    /// along with the path written. The format follows the extension.
+    async fn save_file(
+        mut conf: Config,
+        dir: Option<PathBuf>,
+        pretty: bool,
+    ) -> Result<(String, PathBuf), ToolbarErr> {
+        let f = FileFormat::ALL
+            .iter()
+            .fold(dialog(dir), |dialog, x| {
+                dialog.add_filter(x.name(), x.extensions())
+            })
+            .save_file()
+            .await;
+
+        let Some(f) = f else {
+            return Err(ToolbarErr::NoFileSelected);
+        };
+        let format = FileFormat::from_path(f.path());
+        let timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        conf.mark_saved(&timestamp);
+        let serialized = format.write(&conf, pretty).map_err(ToolbarErr::Serialize)?;
+        // JSON has no NaN or infinity; serde writes them as null, which
+        // would leave a file that can't be opened
+        format
+            .read(serialized.as_bytes())
+            .and_then(|raw| Config::deserialize(&raw).map_err(FormatError::Json))
+            .map_err(ToolbarErr::RoundTrip)?;
+
+        utils::write_atomic(f.path(), serialized.as_bytes()).map_err(ToolbarErr::FileIoError)?;
+
+        let sidecar = format!(
+            "{}  {}\n",
+            checksum(serialized.as_bytes()),
+            file_name(f.path())
+        );
+        utils::write_atomic(&checksum_path(f.path()), sidecar.as_bytes())
+            .map_err(ToolbarErr::FileIoError)?;
+        Ok((timestamp, f.path().to_path_buf()))
+    }
+}
+
+/// Result message for a failed write; closing the dialog isn't a failure
+fn failed(action: &str, e: ToolbarErr) -> Message {
+    match e {
+        ToolbarErr::NoFileSelected => ToolbarMsg::Cancelled.into(),
+        e => ToolbarMsg::Error(format!("{action} failed: {e}")).into(),
+    }
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    std::fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// File dialog starting in the workspace directory, if one is set
+fn dialog(dir: Option<PathBuf>) -> rfd::AsyncFileDialog {
+    match dir {
+        Some(dir) => rfd::AsyncFileDialog::new().set_directory(dir),
+        None => rfd::AsyncFileDialog::new(),
+    }
+}
+
+/// Sidecar holding the checksum of a saved config, in `sha256sum` format
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn checksum(bytes: &[u8]) -> String {
+    sha2::Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Compares the file contents against the sidecar written on save. Files
+/// without a sidecar (e.g. written by hand) are accepted silently.
+fn verify_checksum(path: &Path, bytes: &[u8]) -> Option<String> {
+    let sidecar = std::fs::read_to_string(checksum_path(path)).ok()?;
+    let expected = sidecar.split_whitespace().next()?;
+    if expected.eq_ignore_ascii_case(&checksum(bytes)) {
+        None
+    } else {
+        Some(format!(
+            "{} does not match the checksum recorded when it was saved; it may have been edited or truncated.",
+            file_name(path)
+        ))
+    }
 }
@@ -1,55 +1,146 @@
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-#[serde(transparent)]
+use std::collections::HashMap;
+
+/// Wraps a value with its position among its siblings. The position is
+/// serialized alongside the value's own fields so order survives a
+/// save/open round trip.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
 pub struct TimeSortedContainer<T> {
+    #[serde(flatten)]
     pub val: T,
-    #[serde(skip, default="default_instant")]
-    time: std::time::Instant,
-}
-
-fn default_instant() -> std::time::Instant {
-    std::time::Instant::now()
+    // Position in the editor. Not written out: maps of these are saved in
+    // display order (see serialize_in_order), so the file is the plain
+    // value, as in files from before positions were kept, and needs no
+    // new format version. Still read, from files that wrote it.
+    #[serde(default, skip_serializing)]
+    pub order: u64,
 }
 
-impl<'de, T: serde::Serialize + serde::Deserialize<'de>>  From<T> for TimeSortedContainer<T> {
+impl<'de, T: serde::Serialize + serde::Deserialize<'de>> From<T> for TimeSortedContainer<T> {
     fn from(value: T) -> Self {
         Self {
             val: value,
-            time: std::time::Instant::now(),
+            order: 0,
         }
     }
 }
 
-impl<T> PartialEq for TimeSortedContainer<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.time.eq(&other.time)
+impl<T: Default> Default for TimeSortedContainer<T> {
+    fn default() -> Self {
+        Self {
+            val: T::default(),
+            order: 0,
+        }
     }
 }
 
-impl<T> Eq for TimeSortedContainer<T> {}
+/// Something kept in a map but shown in an order of the user's choosing
+pub trait Ordered {
+    fn order(&self) -> u64;
+    fn set_order(&mut self, order: u64);
+}
+
+impl<T> Ordered for TimeSortedContainer<T> {
+    fn order(&self) -> u64 {
+        self.order
+    }
 
-impl<T> PartialOrd for TimeSortedContainer<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        return self.time.partial_cmp(&other.time) 
+    fn set_order(&mut self, order: u64) {
+        self.order = order;
     }
 }
 
-impl<T> Ord for TimeSortedContainer<T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        return self.time.cmp(&other.time) 
+/// Entries of `map` in display order, ties broken by key
+pub fn sorted<T: Ordered>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+    let mut sorted: Vec<(&String, &T)> = map.iter().collect();
+    sorted.sort_by(|x, y| x.1.order().cmp(&y.1.order()).then(x.0.cmp(y.0)));
+    sorted
+}
+
+/// Position a new entry of `map` goes in: after every other
+pub fn next_order<T: Ordered>(map: &HashMap<String, T>) -> u64 {
+    map.values().map(|x| x.order() + 1).max().unwrap_or(0)
+}
+
+/// Writes a map in display order, so the file lists entries the way the
+/// editor shows them
+pub fn serialize_in_order<S: serde::Serializer, T: Ordered + serde::Serialize>(
+    map: &HashMap<String, T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(sorted(map))
+}
+
+/// Reads a map written by [`serialize_in_order`], numbering the entries
+/// 0, 1, ... by their saved order. Entries with the same saved order, as
+/// in files from before it was saved, keep the order the file lists them in.
+pub fn deserialize_numbered<
+    'de,
+    D: serde::Deserializer<'de>,
+    T: Ordered + serde::Deserialize<'de>,
+>(
+    deserializer: D,
+) -> Result<HashMap<String, T>, D::Error> {
+    struct Entries<T>(std::marker::PhantomData<T>);
+    impl<'de, T: Ordered + serde::Deserialize<'de>> serde::de::Visitor<'de> for Entries<T> {
+        type Value = Vec<(String, T)>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a map")
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(
+            self,
+            mut map: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut entries = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
     }
+    let mut entries = deserializer.deserialize_map(Entries::<T>(std::marker::PhantomData))?;
+    entries.sort_by_key(|(_, x)| x.order());
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key, mut value))| {
+            value.set_order(i as u64);
+            (key, value)
+        })
+        .collect())
 }
 
-impl<T: Default> Default for TimeSortedContainer<T> {
-    fn default() -> Self {
-        Self {
-            val: T::default(),
-            time: std::time::Instant::now(),
+/// Moves the item at `from` in `keys` (already in display order) to
+/// `to`, renumbering every item so ties from older files are resolved.
+/// Out of range moves are ignored.
+pub fn reorder<K: std::hash::Hash + Eq, T: Ordered>(
+    map: &mut HashMap<K, T>,
+    mut keys: Vec<K>,
+    from: usize,
+    to: usize,
+) {
+    if from >= keys.len() || to >= keys.len() {
+        return;
+    }
+    let key = keys.remove(from);
+    keys.insert(to, key);
+    for (i, key) in keys.iter().enumerate() {
+        if let Some(item) = map.get_mut(key) {
+            item.set_order(i as u64);
         }
     }
 }
 
+/// Parses a number typed into a field. "inf", "nan" and values too large
+/// for an f64 parse fine in Rust but can't be written to JSON (they'd
+/// save as null and break the file), so they are refused like any other
+/// bad input.
+pub fn parse_finite(text: &str) -> Option<f64> {
+    text.trim().parse::<f64>().ok().filter(|x| x.is_finite())
+}
 
-pub fn rand_name<F: Fn(&String) -> bool>(test: F) -> String{
+pub fn rand_name<F: Fn(&String) -> bool>(test: F) -> String {
     let mut rand_byte: u8 = rand::random();
     let mut rand_name = format!("group_{:x}", rand_byte);
     while test(&rand_name) {
@@ -59,3 +150,117 @@ pub fn rand_name<F: Fn(&String) -> bool>(test: F) -> String{
 
     rand_name
 }
+
+/// Characters that are reserved in file names on some platform
+const UNSAFE_FILE_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// `name` with characters that can't appear in a file name, on any
+/// platform, replaced by `_`. Exports build file names from group and mode
+/// names through this.
+pub fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_control() || UNSAFE_FILE_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Replaces the file at `path` with `bytes` without ever leaving it half
+/// written. The bytes go to a temporary file next to `path`, which is
+/// renamed over it once fully on disk; if anything fails first, the old
+/// file is untouched. The temporary file shares `path`'s directory, so the
+/// rename never crosses filesystems; if it fails, that error is returned.
+pub fn write_atomic(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{name}.{:08x}.tmp", rand::random::<u32>()));
+
+    let written = std::fs::File::create_new(&temp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    let replaced = written.and_then(|()| std::fs::rename(&temp, path));
+    // Gone already after a successful rename
+    let _ = std::fs::remove_file(&temp);
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh empty directory for one test
+    fn scratch() -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rf_config_generator_{:08x}", rand::random::<u32>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn temp_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|x| x.unwrap().path())
+            .filter(|x| x.extension().is_some_and(|x| x == "tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn parse_finite_refuses_what_json_cannot_hold() {
+        assert_eq!(parse_finite(" 2.4e9 "), Some(2.4e9));
+        assert_eq!(parse_finite("-3"), Some(-3.0));
+        for text in [
+            "inf", "-inf", "infinity", "NaN", "nan", "1e400", "-1e400", "", "12abc",
+        ] {
+            assert_eq!(parse_finite(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn sanitize_name_replaces_path_separators() {
+        assert_eq!(sanitize_name("ism/433"), "ism_433");
+        assert_eq!(sanitize_name(r"..\fm\band"), ".._fm_band");
+        assert_eq!(sanitize_name("tab\there"), "tab_here");
+        assert_eq!(sanitize_name("ISM 433-band (EU)"), "ISM 433-band (EU)");
+    }
+
+    #[test]
+    fn write_atomic_replaces_file() {
+        let dir = scratch();
+        let path = dir.join("config.json");
+        std::fs::write(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(temp_files(&dir).is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_write_atomic_leaves_everything_as_it_was() {
+        let dir = scratch();
+        // Neither renamed nor copied over, as it is a directory
+        let path = dir.join("config.json");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("kept"), b"original").unwrap();
+        assert!(write_atomic(&path, b"new").is_err());
+        assert_eq!(std::fs::read(path.join("kept")).unwrap(), b"original");
+        assert!(temp_files(&dir).is_empty());
+
+        // No temporary file can be made where the parent is a file
+        let file = dir.join("file");
+        std::fs::write(&file, b"original").unwrap();
+        assert!(write_atomic(&file.join("config.json"), b"new").is_err());
+        assert_eq!(std::fs::read(&file).unwrap(), b"original");
+        assert!(temp_files(&dir).is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{Message, utils};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in the config. Each section of the config reports its
+/// own issues through a `validate` method, and `Config::validate` gathers
+/// them for the panel.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub section: &'static str,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn warning(section: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            section,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(section: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            section,
+            message: message.into(),
+        }
+    }
+}
+
+/// Warns when a name would be changed on its way into an export's file name
+pub fn file_name_issue(section: &'static str, what: &str, name: &str) -> Option<ValidationIssue> {
+    let sanitized = crate::utils::sanitize_name(name);
+    (sanitized != name).then(|| {
+        ValidationIssue::warning(
+            section,
+            format!("{what} '{name}' has characters that can't go in file names; exports will use '{sanitized}'"),
+        )
+    })
+}
+
+/// Text style for warnings; iced only ships a danger style
+pub fn warning(_theme: &iced::Theme) -> iced::widget::text::Style {
+    iced::widget::text::Style {
+        color: Some(iced::Color::from_rgb(0.9, 0.6, 0.0)),
+    }
+}
+
+/// Text input style with a red border, for fields holding a bad value
+pub fn invalid_input(
+    theme: &iced::Theme,
+    status: iced::widget::text_input::Status,
+) -> iced::widget::text_input::Style {
+    let mut style = iced::widget::text_input::default(theme, status);
+    style.border.color = theme.palette().danger;
+    style
+}
+
+/// Text input style with an amber border, for fields worth a second look
+pub fn warning_input(
+    theme: &iced::Theme,
+    status: iced::widget::text_input::Status,
+) -> iced::widget::text_input::Style {
+    let mut style = iced::widget::text_input::default(theme, status);
+    style.border.color = iced::Color::from_rgb(0.9, 0.6, 0.0);
+    style
+}
+
+/// Text input for a number field holding `value`. `raw` is what was last
+/// typed into it: shown as is while it doesn't parse, with a red border,
+/// or while it reads as `value` (so "1e6" isn't rewritten as you type),
+/// and otherwise replaced by `value`. `style` applies while the text parses.
+pub fn number_input<'a, M: Clone + 'a>(
+    placeholder: &str,
+    value: f64,
+    raw: Option<&str>,
+    style: fn(&iced::Theme, iced::widget::text_input::Status) -> iced::widget::text_input::Style,
+) -> iced::widget::TextInput<'a, M> {
+    let parsed = raw.map(utils::parse_finite);
+    let text = match (raw, parsed) {
+        (Some(raw), Some(None)) => raw.to_string(),
+        (Some(raw), Some(Some(x))) if x == value => raw.to_string(),
+        _ => format!("{value}"),
+    };
+    let invalid = matches!(parsed, Some(None));
+    iced::widget::text_input(placeholder, &text).style(if invalid { invalid_input } else { style })
+}
+
+/// The number typed into a digits-only field. An empty field reads as 0;
+/// anything with a non-digit in it (e.g. "2a"), or too large for a u64,
+/// is refused.
+pub fn parse_digits(text: &str) -> Option<u64> {
+    if text.is_empty() {
+        return Some(0);
+    }
+    text.chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+/// Text input for a whole number. Keystrokes that would leave anything but
+/// digits in it are refused, so it always shows `value`; the caption under
+/// it says so, along with `range` when there is one. A value outside
+/// `range` turns the input red.
+pub fn digits_input<'a>(
+    placeholder: &str,
+    value: u64,
+    range: Option<std::ops::RangeInclusive<u64>>,
+    on_change: impl Fn(u64) -> Message + 'a,
+) -> iced::Element<'a, Message> {
+    let in_range = range.as_ref().is_none_or(|x| x.contains(&value));
+    let caption = match &range {
+        Some(range) => format!("digits only, {} to {}", range.start(), range.end()),
+        None => "digits only".to_string(),
+    };
+    iced::widget::column![
+        iced::widget::text_input(placeholder, &value.to_string())
+            .on_input(move |c| parse_digits(&c).map_or(Message::None, &on_change))
+            .style(if in_range {
+                iced::widget::text_input::default
+            } else {
+                invalid_input
+            }),
+        iced::widget::text(caption).size(12).style(if in_range {
+            iced::widget::text::default
+        } else {
+            iced::widget::text::danger
+        }),
+    ]
+    .into()
+}
+
+pub fn view<'a>(issues: &[ValidationIssue]) -> iced::Element<'a, Message> {
+    if issues.is_empty() {
+        return iced::widget::text("No problems found").into();
+    }
+    iced::widget::column(issues.iter().map(|issue| {
+        let text = iced::widget::text(format!("[{}] {}", issue.section, issue.message));
+        match issue.severity {
+            Severity::Error => text.style(iced::widget::text::danger),
+            Severity::Warning => text.style(warning),
+        }
+        .into()
+    }))
+    .spacing(4)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_field_refuses_non_digits() {
+        assert_eq!(parse_digits("2a"), None);
+        assert_eq!(parse_digits("2"), Some(2));
+        assert_eq!(parse_digits(""), Some(0));
+        assert_eq!(parse_digits("-2"), None);
+        assert_eq!(parse_digits("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn names_with_slashes_are_flagged() {
+        let issue = file_name_issue("Frequencies", "group", "ism/433").unwrap();
+        assert_eq!(issue.severity, Severity::Warning);
+        assert!(issue.message.contains("'ism_433'"), "{}", issue.message);
+        let issue = file_name_issue("Collection Modes", "mode", r"wide\band").unwrap();
+        assert!(issue.message.contains("'wide_band'"), "{}", issue.message);
+        assert!(file_name_issue("Frequencies", "group", "ism_433 (EU)").is_none());
+    }
+}
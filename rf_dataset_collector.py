@@ -245,8 +245,10 @@ class DatasetCollector:
         total_captures = sum(len(freqs) for freqs in self.config["frequencies"].values())
         capture_count = 0
         
-        # Sweep through all frequency bands
-        for band_name, frequencies in self.config["frequencies"].items():
+        # Sweep through all frequency bands, higher priority bands first
+        priorities = self.config.get("group_priorities", {})
+        bands = sorted(self.config["frequencies"].items(), key=lambda band: -priorities.get(band[0], 0))
+        for band_name, frequencies in bands:
             print(f"\n=== {band_name.upper()} ===")
             
             for freq_info in frequencies:
@@ -439,6 +441,8 @@ class DatasetCollector:
                 current_time = datetime.now().strftime("%H:%M")
                 time_slot = "unknown"
                 for slot in self.config["scheduling"]["time_slots"]:
+                    if not slot.get("enabled", True):
+                        continue
                     if slot["start"] <= current_time <= slot["end"]:
                         time_slot = slot["name"]
                         break
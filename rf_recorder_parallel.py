@@ -26,6 +26,12 @@ try:
 except ImportError:
     ZSTD_AVAILABLE = False
 
+try:
+    import lz4.frame
+    LZ4_AVAILABLE = True
+except ImportError:
+    LZ4_AVAILABLE = False
+
 class ParallelCompressor:
     """Handles parallel compression with separate threads for compression and I/O"""
     
@@ -57,6 +63,10 @@ class ParallelCompressor:
             if not ZSTD_AVAILABLE:
                 raise ValueError("zstandard library not installed")
             self.file_handle = open(filename + '.zst', 'wb')
+        elif compression == 'lz4':
+            if not LZ4_AVAILABLE:
+                raise ValueError("lz4 library not installed")
+            self.file_handle = open(filename + '.lz4', 'wb')
         else:
             raise ValueError(f"Unsupported compression method: {compression}")
         
@@ -88,6 +98,9 @@ class ParallelCompressor:
         elif self.compression == 'zstd':
             compressor = zstd.ZstdCompressor(level=self.compression_level)
             return compressor.compress(data_chunk.tobytes())
+        elif self.compression == 'lz4':
+            # Levels 3 and up use the high compression (HC) mode
+            return lz4.frame.compress(data_chunk.tobytes(), compression_level=self.compression_level)
     
     def _io_worker(self):
         """I/O worker thread - writes compressed data to disk"""
@@ -163,7 +176,7 @@ class ParallelCompressor:
         self.file_handle.close()
         
         # Get actual file size
-        extensions = {'gzip': '.gz', 'lzma': '.xz', 'zstd': '.zst', 'none': ''}
+        extensions = {'gzip': '.gz', 'lzma': '.xz', 'zstd': '.zst', 'lz4': '.lz4', 'none': ''}
         compressed_filename = self.filename + extensions.get(self.compression, '')
         actual_file_size = os.path.getsize(compressed_filename) if os.path.exists(compressed_filename) else self.bytes_written
         
@@ -476,7 +489,7 @@ def main():
                        help='Output filename')
     parser.add_argument('-g', '--gain', type=float, default=30,
                        help='RF gain in dB (default: 30)')
-    parser.add_argument('-c', '--compression', choices=['none', 'gzip', 'lzma', 'zstd'],
+    parser.add_argument('-c', '--compression', choices=['none', 'gzip', 'lzma', 'zstd', 'lz4'],
                        default='none', help='Compression method (default: none)')
     parser.add_argument('--compression-level', type=int, default=3,
                        help='Compression level (default: 3)')
@@ -500,6 +513,10 @@ def main():
         print("Error: zstandard library not installed.")
         print("Install with: pip install zstandard")
         sys.exit(1)
+    if args.compression == 'lz4' and not LZ4_AVAILABLE:
+        print("Error: lz4 library not installed.")
+        print("Install with: pip install lz4")
+        sys.exit(1)
     
     # Handle visualization-only mode
     if args.viz_only:
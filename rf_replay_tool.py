@@ -12,7 +12,9 @@ import json
 import os
 import sys
 import threading
+import tempfile
 from datetime import datetime
+from fractions import Fraction
 import subprocess
 
 try:
@@ -21,6 +23,12 @@ try:
 except ImportError:
     ZSTD_AVAILABLE = False
 
+try:
+    import lz4.frame
+    LZ4_AVAILABLE = True
+except ImportError:
+    LZ4_AVAILABLE = False
+
 class RFReplayer:
     """Handles RF data replay through USRP"""
     
@@ -42,6 +50,15 @@ class RFReplayer:
                 decompressed_data = dctx.stream_reader(f).read()
             data = np.frombuffer(decompressed_data, dtype=np.complex64)
             
+        elif filename.endswith('.lz4'):
+            if not LZ4_AVAILABLE:
+                raise ValueError("lz4 library not installed for .lz4 files")
+
+            # The recorder writes one frame per chunk; reading goes through all of them
+            with lz4.frame.open(filename, 'rb') as f:
+                data_bytes = f.read()
+            data = np.frombuffer(data_bytes, dtype=np.complex64)
+            
         elif filename.endswith('.gz'):
             import gzip
             with gzip.open(filename, 'rb') as f:
@@ -100,9 +117,38 @@ class RFReplayer:
             print("No metadata file found - you'll need to specify frequency and sample rate")
             return {}
     
+    def resample(self, data, input_rate, output_rate, max_denominator=1000):
+        """Rational resampling of I/Q data from input_rate to output_rate.
+
+        The rate ratio is reduced to up/down (20 MS/s to 23.04 MS/s is
+        144/125) and scipy's polyphase filter upsamples by up, low-pass
+        filters and keeps every down-th sample, so a tone stays at the same
+        offset from center in Hz. The filter cuts off at the lower of the
+        two Nyquist frequencies: going down in rate drops what the output
+        can't hold instead of aliasing it. A ratio that doesn't reduce to a
+        denominator of at most max_denominator is approximated by the
+        nearest one that does; the rate error is printed, and every offset
+        from center is off by the same relative amount.
+        """
+        from scipy.signal import resample_poly
+
+        ratio = Fraction(output_rate / input_rate).limit_denominator(max_denominator)
+        up, down = ratio.numerator, ratio.denominator
+        actual_rate = input_rate * up / down
+
+        print(f"Resampling {input_rate/1e6:.3f} MS/s -> {output_rate/1e6:.3f} MS/s (x{up}/{down})")
+        if actual_rate != output_rate:
+            error_ppm = (actual_rate / output_rate - 1) * 1e6
+            print(f"  Ratio approximated: effective rate {actual_rate/1e6:.6f} MS/s ({error_ppm:+.1f} ppm)")
+
+        if up == down:
+            return data
+        return resample_poly(data, up, down).astype(np.complex64)
+    
     def replay_data(self, data, center_freq, sample_rate, tx_gain=30, loop=False, 
-                   scale_factor=1.0, delay_between_loops=1.0):
-        """Replay I/Q data through USRP transmitter"""
+                   scale_factor=1.0, delay_between_loops=1.0, repeat=None):
+        """Replay I/Q data through USRP transmitter. With loop, the same
+        buffer is sent again until interrupted, or `repeat` times in total"""
         
         print(f"Initializing USRP for transmission...")
         
@@ -127,7 +173,10 @@ class RFReplayer:
         print(f"  Sample Rate: {actual_rate/1e6:.3f} MS/s")
         print(f"  TX Gain: {actual_gain:.1f} dB")
         print(f"  Scale Factor: {scale_factor}")
-        print(f"  Loop Mode: {'Yes' if loop else 'No'}")
+        if loop and repeat:
+            print(f"  Loop Mode: {repeat} passes")
+        else:
+            print(f"  Loop Mode: {'Yes' if loop else 'No'}")
         
         # Scale the data if requested
         if scale_factor != 1.0:
@@ -188,7 +237,7 @@ class RFReplayer:
                 elapsed = time.time() - start_time
                 print(f"Transmission complete in {elapsed:.2f} seconds")
                 
-                if not loop:
+                if not loop or (repeat and loop_count >= repeat):
                     break
                 
                 if delay_between_loops > 0:
@@ -197,6 +246,14 @@ class RFReplayer:
                 
         except KeyboardInterrupt:
             print(f"\nTransmission stopped by user after {loop_count} loops")
+            # Close the burst cut off mid-send so the radio stops transmitting
+            metadata = uhd.types.TXMetadata()
+            metadata.end_of_burst = True
+            tx_streamer.send(np.zeros(0, dtype=np.complex64), metadata)
+        finally:
+            # Drop the streamer and the device so the SDR is released straight away
+            tx_streamer = None
+            self.usrp = None
         
         print("Replay complete!")
     
@@ -280,17 +337,24 @@ class RFReplayer:
                     print(f"  {file} ({file_size:.1f} MB)")
             return None
 
-    # Visualize the recorded data utilizing GNURadio
-    def visualize(self, iq_dat, rf_fn, center_freq, gain):
-
-        with open(rf_fn + '.temp', 'wb') as f:
-            f.write(iq_dat)
+    # Visualize the recorded data utilizing GNURadio. The decompressed
+    # samples are written to a temporary file once; with loop, GNURadio
+    # re-reads it until the window is closed.
+    def visualize(self, iq_dat, rf_fn, center_freq, sample_rate, gain, loop=False):
 
-        print(f'Opening process with args: {center_freq}, {gain}')
+        fd, temp_fn = tempfile.mkstemp(prefix=os.path.basename(rf_fn) + '.', suffix='.temp')
+        try:
+            with os.fdopen(fd, 'wb') as f:
+                f.write(iq_dat)
 
-        subprocess.call(['python3' , 'replay.py', '--rf-fn', rf_fn + '.temp', '--freq', str(center_freq), '--gain', str(gain)])
+            print(f'Opening process with args: {center_freq}, {sample_rate}, {gain}')
 
-        os.remove(rf_fn + '.temp')
+            subprocess.call(['python3' , 'replay.py', '--rf-fn', temp_fn, '--freq', str(center_freq),
+                             '--samp-rate', str(sample_rate), '--gain', str(gain), '--loop', str(int(loop))])
+        except KeyboardInterrupt:
+            print("\nVisualization stopped by user")
+        finally:
+            os.remove(temp_fn)
 
 
         
@@ -305,9 +369,12 @@ def main():
     parser.add_argument('--dataset', type=str, help='Dataset directory to explore')
     parser.add_argument('--freq', type=float, help='Center frequency (Hz)')
     parser.add_argument('--rate', type=float, help='Sample rate (Hz)')
+    parser.add_argument('--output-rate', type=float,
+                        help='Resample to this rate (Hz) before transmitting or visualizing')
     parser.add_argument('--gain', type=float, default=30, help='TX gain (dB)')
     parser.add_argument('--scale', type=float, default=1.0, help='Signal scale factor')
     parser.add_argument('--loop', action='store_true', help='Loop the replay continuously')
+    parser.add_argument('--repeat', type=int, help='Loop the transmission N times, then stop (implies --loop)')
     parser.add_argument('--delay', type=float, default=1.0, help='Delay between loops (seconds)')
     parser.add_argument('--analyze-only', action='store_true', help='Only analyze data, don\'t transmit')
     parser.add_argument('--list-only', action='store_true', help='Only list available captures')
@@ -361,15 +428,24 @@ def main():
         if args.analyze_only:
             return
         
+        # Match the rate the SDR runs at; the capture's own rate comes from
+        # --rate or its .meta sidecar
+        if args.output_rate and args.output_rate != sample_rate:
+            data = replayer.resample(data, sample_rate, args.output_rate)
+            sample_rate = args.output_rate
+
+        # The capture is decompressed once above; every loop reuses it
+        loop = args.loop or args.repeat is not None
+
         if args.transmit:
             # Replay the data
             replayer.replay_data(
                 data, center_freq, sample_rate, 
-                args.gain, args.loop, args.scale, args.delay
+                args.gain, loop, args.scale, args.delay, args.repeat
             )
 
         if not args.transmit:
-            replayer.visualize(data, args.file, center_freq, args.gain)
+            replayer.visualize(data, args.file, center_freq, sample_rate, args.gain, loop)
         
     except Exception as e:
         print(f"Error: {e}")
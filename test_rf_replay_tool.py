@@ -0,0 +1,60 @@
+#!/usr/bin/env python3
+"""
+Tests for rf_replay_tool that run without an SDR attached.
+
+Run from the repository root with: python3 -m unittest test_rf_replay_tool
+"""
+
+import sys
+import types
+import unittest
+
+try:
+    import numpy as np
+    import scipy.signal  # noqa: F401 - needed by RFReplayer.resample
+except ImportError:
+    np = None
+
+
+def tone(offset_hz, sample_rate, duration=0.01):
+    """A complex tone offset_hz from center, as a capture would hold it"""
+    t = np.arange(int(sample_rate * duration)) / sample_rate
+    return np.exp(2j * np.pi * offset_hz * t).astype(np.complex64)
+
+
+def peak_hz(data, sample_rate):
+    """Offset from center of the strongest FFT bin, and the bin width"""
+    spectrum = np.abs(np.fft.fft(data * np.hanning(len(data))))
+    freqs = np.fft.fftfreq(len(data), d=1 / sample_rate)
+    return freqs[np.argmax(spectrum)], sample_rate / len(data)
+
+
+@unittest.skipIf(np is None, "numpy and scipy are needed to test resampling")
+class ResampleTest(unittest.TestCase):
+
+    def setUp(self):
+        # The UHD driver is only used to transmit; a stand-in lets the tool import
+        sys.modules.setdefault("uhd", types.ModuleType("uhd"))
+        from rf_replay_tool import RFReplayer
+        self.replayer = RFReplayer()
+
+    def test_tone_keeps_its_offset_from_20_to_23_04_msps(self):
+        for offset in (3.1e6, -5e6, 250e3):
+            resampled = self.replayer.resample(tone(offset, 20e6), 20e6, 23.04e6)
+            # 144/125 of the input samples, give or take filter edges
+            self.assertAlmostEqual(len(resampled), 200000 * 144 / 125, delta=1)
+            peak, bin_width = peak_hz(resampled, 23.04e6)
+            self.assertLessEqual(abs(peak - offset), bin_width, f"tone at {offset} Hz moved to {peak} Hz")
+
+    def test_tone_keeps_its_offset_going_down_in_rate(self):
+        resampled = self.replayer.resample(tone(-2.5e6, 23.04e6), 23.04e6, 20e6)
+        peak, bin_width = peak_hz(resampled, 20e6)
+        self.assertLessEqual(abs(peak + 2.5e6), bin_width)
+
+    def test_equal_rates_leave_data_alone(self):
+        data = tone(1e6, 20e6)
+        self.assertIs(self.replayer.resample(data, 20e6, 20e6), data)
+
+
+if __name__ == "__main__":
+    unittest.main()